@@ -8,8 +8,11 @@ pub struct Payload {
     pub configuration: Config,
 }
 
-#[derive(Clone, Debug, Deserialize)]
-pub struct Config {}
+// configuration fields are generated from what's defined in script.config.yml.
+mod config {
+    include!(concat!(env!("OUT_DIR"), "/config.rs"));
+}
+pub use config::*;
 
 #[derive(Clone, Debug, Deserialize)]
 // Use the following container attribute if fields need to be camel cased.
@@ -20,7 +23,29 @@ pub struct Input {
 }
 
 #[derive(Clone, Debug, Deserialize)]
-pub struct PurchaseProposal {}
+pub struct PurchaseProposal {
+    #[serde(default)]
+    pub merchandise_lines: Vec<MerchandiseLine>,
+    #[serde(default)]
+    pub shipping_address: Option<Address>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct MerchandiseLine {
+    pub quantity: u32,
+    pub price: Money,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Money {
+    pub subunits: u64,
+    pub currency: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Address {
+    pub country_code: String,
+}
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct PaymentMethod {
@@ -33,14 +58,14 @@ pub struct FunctionResult {
     pub operations: Vec<Operation>,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, PartialEq)]
 pub struct Operation {
     pub hide: Option<HideOperation>,
     pub r#move: Option<MoveOperation>,
     pub rename: Option<RenameOperation>,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, PartialEq)]
 pub struct HideOperation {
     pub payment_method_id: ID,
 }
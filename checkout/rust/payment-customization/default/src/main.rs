@@ -1,49 +1,74 @@
-use serde::Serialize;
-
 /*
- * This script provides a basic example of logging, deserialize input and
- * configuration values, and deserialize output (that leaves payment methods unchanged).
+ * This script hides, moves and renames payment methods following the `rules` from the
+ * configuration, e.g. "hide the methods matching `Cash*` when the total is above 100.00 CAD and
+ * the checkout ships to Canada". Rules are evaluated in order; when two rules target the same
+ * payment method, the first one wins and the conflict is logged.
  */
 
 mod api;
+mod rules;
+#[cfg(test)]
+mod properties;
 use api::*;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn main() -> std::process::ExitCode {
     // use stderr to print output
     eprintln!("Hello World");
 
     // read from stdin and write to stdout
-    let payload: Payload = serde_json::from_reader(std::io::BufReader::new(std::io::stdin()))?;
-    let mut out = std::io::stdout();
-    let mut serializer = serde_json::Serializer::new(&mut out);
-    script(payload)?.serialize(&mut serializer)?;
-    Ok(())
+    shopify_function_core::run(script)
 }
 
-fn script(payload: Payload) -> Result<FunctionResult, Box<dyn std::error::Error>> {
-    let (input, _config) = (payload.input, payload.configuration);
-    return Ok(build_result(input.payment_methods[0].id.clone()));
-}
+pub(crate) fn script(payload: Payload) -> Result<FunctionResult, Box<dyn std::error::Error>> {
+    let (input, config) = (payload.input, payload.configuration);
+
+    // the rules are a JSON list
+    let rules: Vec<rules::Rule> = match config.rules.as_deref() {
+        Some(rules) => serde_json::from_str(rules)?,
+        None => vec![],
+    };
 
-fn build_result(payment_method_to_remove: ID) -> FunctionResult {
-    FunctionResult {
-        operations: vec![Operation {
-            hide: Some(HideOperation {
-                payment_method_id: payment_method_to_remove,
-            }),
-            r#move: None,
-            rename: None,
-        }],
+    let evaluation = rules::evaluate(&rules, &input)?;
+    for conflict in &evaluation.conflicts {
+        eprintln!("{}", conflict);
     }
+
+    Ok(build_result(evaluation.operations))
+}
+
+fn build_result(operations: Vec<Operation>) -> FunctionResult {
+    FunctionResult { operations }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use shopify_function_core::fixtures;
+
     fn default_payload() -> Payload {
         Payload {
             input: Input {
-                purchase_proposal: PurchaseProposal {},
+                purchase_proposal: PurchaseProposal {
+                    merchandise_lines: vec![
+                        MerchandiseLine {
+                            quantity: 2,
+                            price: Money {
+                                subunits: 5000,
+                                currency: "CAD".to_string(),
+                            },
+                        },
+                        MerchandiseLine {
+                            quantity: 3,
+                            price: Money {
+                                subunits: 1000,
+                                currency: "CAD".to_string(),
+                            },
+                        },
+                    ],
+                    shipping_address: Some(Address {
+                        country_code: "CA".to_string(),
+                    }),
+                },
                 payment_methods: vec![
                     PaymentMethod {
                         id: "123456789".to_string(),
@@ -59,15 +84,29 @@ mod tests {
                     },
                 ],
             },
-            configuration: Config {},
+            configuration: Config { rules: None },
         }
     }
 
+    fn payload_with_rules(rules: &str) -> Payload {
+        let mut payload = default_payload();
+        payload.configuration.rules = Some(rules.to_string());
+        payload
+    }
+
     #[test]
-    fn test_result_contains_hide_operation() {
+    fn test_result_is_empty_without_rules() {
         let payload = default_payload();
         let operations = script(payload).unwrap().operations;
 
+        assert!(operations.is_empty());
+    }
+
+    #[test]
+    fn test_result_contains_hide_operation() {
+        let payload = payload_with_rules(r#"[{"hide": {"name": "Shopify payments"}}]"#);
+        let operations = script(payload).unwrap().operations;
+
         assert_eq!(operations.len(), 1);
         assert_eq!(
             operations[0].hide.as_ref().unwrap().payment_method_id,
@@ -76,4 +115,122 @@ mod tests {
         assert_eq!(operations[0].rename, None);
         assert_eq!(operations[0].r#move, None);
     }
+
+    #[test]
+    fn test_hide_rule_conditions() {
+        let rule = r#"[{
+            "when": {"totalAbove": {"amount": "129.99", "currencyCode": "CAD"}, "countryCodes": ["CA", "US"]},
+            "hide": {"name": "cash*"}
+        }]"#;
+        let operations = script(payload_with_rules(rule)).unwrap().operations;
+        assert_eq!(operations.len(), 1);
+        assert_eq!(
+            operations[0].hide.as_ref().unwrap().payment_method_id,
+            "523414132"
+        );
+
+        // 2 x 50.00 + 3 x 10.00 isn't above 130.00.
+        let rule = rule.replace("129.99", "130.00");
+        assert!(script(payload_with_rules(&rule))
+            .unwrap()
+            .operations
+            .is_empty());
+
+        let rule = r#"[{"when": {"countryCodes": ["US"]}, "hide": {"name": "cash*"}}]"#;
+        assert!(script(payload_with_rules(rule))
+            .unwrap()
+            .operations
+            .is_empty());
+
+        let mut payload = payload_with_rules(
+            r#"[{"when": {"countryCodes": ["CA"]}, "hide": {"name": "cash*"}}]"#,
+        );
+        payload.input.purchase_proposal.shipping_address = None;
+        assert!(script(payload).unwrap().operations.is_empty());
+
+        // Checkouts in another currency never reach the total.
+        let rule = r#"[{"when": {"totalAbove": {"amount": "1", "currencyCode": "USD"}}, "hide": {"name": "cash*"}}]"#;
+        assert!(script(payload_with_rules(rule))
+            .unwrap()
+            .operations
+            .is_empty());
+    }
+
+    #[test]
+    fn test_move_and_rename_rules() {
+        let payload = payload_with_rules(
+            r#"[
+                {"move": {"name": "Cash on Delivery", "index": 0}},
+                {"rename": {"name": "Auth.net", "to": "Credit card"}}
+            ]"#,
+        );
+        let operations = script(payload).unwrap().operations;
+
+        assert_eq!(
+            operations,
+            vec![
+                Operation {
+                    hide: None,
+                    r#move: Some(MoveOperation {
+                        payment_method_id: "523414132".to_string(),
+                        index: 0,
+                    }),
+                    rename: None,
+                },
+                Operation {
+                    hide: None,
+                    r#move: None,
+                    rename: Some(RenameOperation {
+                        payment_method_id: "987654321".to_string(),
+                        name: "Credit card".to_string(),
+                    }),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_first_rule_wins_conflicts() {
+        let payload = payload_with_rules(
+            r#"[
+                {"rename": {"name": "Auth.net", "to": "Credit card"}},
+                {"hide": {"name": "*"}}
+            ]"#,
+        );
+        let evaluation = rules::evaluate(
+            &serde_json::from_str::<Vec<rules::Rule>>(
+                payload.configuration.rules.as_ref().unwrap(),
+            )
+            .unwrap(),
+            &payload.input,
+        )
+        .unwrap();
+
+        assert_eq!(evaluation.operations.len(), 3);
+        assert!(evaluation.operations[0].rename.is_some());
+        assert!(evaluation.operations[1..]
+            .iter()
+            .all(|operation| operation.hide.is_some()));
+        assert_eq!(
+            evaluation.conflicts,
+            vec![rules::Conflict {
+                payment_method_id: "987654321".to_string(),
+                applied: 0,
+                skipped: 1,
+            }]
+        );
+        assert_eq!(script(payload).unwrap().operations, evaluation.operations);
+    }
+
+    #[test]
+    fn test_invalid_rules_are_an_error() {
+        let payload = payload_with_rules(r#"[{"delete": {"name": "*"}}]"#);
+
+        assert!(script(payload).is_err());
+    }
+
+    #[test]
+    fn test_fixtures() {
+        fixtures::check("tests/fixtures", script);
+    }
 }
@@ -0,0 +1,115 @@
+//! Properties of the rules on random checkouts and rules, including rules
+//! that aren't valid JSON.
+
+use proptest::prelude::*;
+use serde_json::{json, Value as Json};
+use shopify_function_core::strategies;
+
+use super::*;
+
+const NAMES: [&str; 3] = ["Shopify payments", "Auth.net", "Cash on Delivery"];
+const PATTERNS: [&str; 5] = ["*", "Cash*", "shopify payments", "*.net", "Gift card"];
+const CURRENCIES: [&str; 2] = ["CAD", "USD"];
+const COUNTRY_CODES: [&str; 3] = ["CA", "US", "FR"];
+
+fn rule() -> impl Strategy<Value = Json> {
+    let total_above = (strategies::amount(), prop::sample::select(&CURRENCIES[..])).prop_map(
+        |(amount, currency_code)| json!({ "amount": amount, "currencyCode": currency_code }),
+    );
+    let action = (
+        prop::sample::select(&PATTERNS[..]),
+        0..2u8,
+        0..4u64,
+        "[A-Za-z ]{1,12}",
+    )
+        .prop_map(|(name, kind, index, to)| match kind {
+            0 => json!({ "hide": { "name": name } }),
+            1 => json!({ "move": { "name": name, "index": index } }),
+            _ => json!({ "rename": { "name": name, "to": to } }),
+        });
+    (
+        prop::option::of(total_above),
+        prop::sample::subsequence(&COUNTRY_CODES[..], 0..=2),
+        action,
+    )
+        .prop_map(|(total_above, country_codes, mut rule)| {
+            rule["when"] = json!({ "totalAbove": total_above, "countryCodes": country_codes });
+            rule
+        })
+}
+
+/// The `rules` configuration, with whether it is valid.
+fn rules() -> impl Strategy<Value = (Option<String>, bool)> {
+    prop_oneof![
+        1 => Just((None, true)),
+        4 => prop::collection::vec(rule(), 0..4)
+            .prop_map(|rules| (Some(Json::from(rules).to_string()), true)),
+        1 => strategies::json().prop_map(|rules| (Some(rules.to_string()), false)),
+        1 => ".{0,20}".prop_map(|rules| (Some(rules), false)),
+    ]
+}
+
+fn payload() -> impl Strategy<Value = (Payload, bool)> {
+    let line = (0..10u32, 0..100_000u64, prop::sample::select(&CURRENCIES[..]))
+        .prop_map(|(quantity, subunits, currency)| {
+            json!({ "quantity": quantity, "price": { "subunits": subunits, "currency": currency } })
+        });
+    (
+        prop::collection::vec(line, 0..4),
+        prop::option::of(prop::sample::select(&COUNTRY_CODES[..])),
+        prop::sample::subsequence(&NAMES[..], 0..=3),
+        rules(),
+    )
+        .prop_map(|(merchandise_lines, country_code, names, (rules, valid))| {
+            let payment_methods: Vec<Json> = names
+                .into_iter()
+                .enumerate()
+                .map(|(id, name)| json!({ "id": id.to_string(), "name": name }))
+                .collect();
+            let payload = serde_json::from_value(json!({
+                "input": {
+                    "purchase_proposal": {
+                        "merchandise_lines": merchandise_lines,
+                        "shipping_address": country_code.map(|code| json!({ "country_code": code })),
+                    },
+                    "payment_methods": payment_methods,
+                },
+                "configuration": { "rules": rules },
+            }))
+            .expect("the payload deserializes");
+            (payload, valid)
+        })
+}
+
+proptest! {
+    #[test]
+    fn test_valid_rules_never_fail((payload, valid) in payload()) {
+        let result = script(payload);
+
+        if valid {
+            prop_assert!(result.is_ok(), "{}", result.unwrap_err());
+        }
+    }
+
+    #[test]
+    fn test_each_payment_method_gets_one_operation((payload, _) in payload()) {
+        let ids: Vec<ID> = payload.input.payment_methods.iter().map(|method| method.id.clone()).collect();
+        let operations = match script(payload) {
+            Ok(result) => result.operations,
+            Err(_) => return Ok(()),
+        };
+
+        let mut targeted: Vec<&ID> = vec![];
+        for operation in &operations {
+            let id = match (&operation.hide, &operation.r#move, &operation.rename) {
+                (Some(hide), None, None) => &hide.payment_method_id,
+                (None, Some(r#move), None) => &r#move.payment_method_id,
+                (None, None, Some(rename)) => &rename.payment_method_id,
+                _ => panic!("{:?} doesn't have exactly one action", operation),
+            };
+            prop_assert!(ids.contains(id), "{} isn't a payment method", id);
+            prop_assert!(!targeted.contains(&id), "{} is targeted twice", id);
+            targeted.push(id);
+        }
+    }
+}
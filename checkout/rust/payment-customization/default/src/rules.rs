@@ -0,0 +1,256 @@
+use std::fmt;
+
+use serde::Deserialize;
+use shopify_function_core::{money, CurrencyCode, Decimal};
+
+use super::api::*;
+
+/// A rule from the `rules` configuration field, e.g.
+/// `{"when": {"totalAbove": {"amount": "100.00", "currencyCode": "CAD"}, "countryCodes": ["CA"]}, "hide": {"name": "Cash*"}}`.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Rule {
+    #[serde(default)]
+    pub when: Conditions,
+    #[serde(flatten)]
+    pub action: Action,
+}
+
+/// What a rule does to the payment methods whose name matches `name`.
+///
+/// Names are matched case-insensitively, and `*` matches any characters, e.g.
+/// `"Cash*"` matches `"Cash on Delivery"`.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum Action {
+    Hide { name: String },
+    Move { name: String, index: u64 },
+    Rename { name: String, to: String },
+}
+
+/// The conditions a checkout must meet for a rule to apply. A rule without
+/// conditions always applies.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Conditions {
+    /// The checkout total must be greater than this amount, and in its currency.
+    #[serde(default)]
+    pub total_above: Option<money::Money>,
+    /// The checkout must ship to one of these countries.
+    #[serde(default)]
+    pub country_codes: Vec<String>,
+}
+
+impl Conditions {
+    fn are_met(&self, purchase_proposal: &PurchaseProposal) -> Result<bool, money::MoneyError> {
+        if let Some(minimum) = &self.total_above {
+            match total_price(purchase_proposal, minimum.currency_code)? {
+                Some(total) if total > *minimum => {}
+                _ => return Ok(false),
+            }
+        }
+        if !self.country_codes.is_empty() {
+            let country_code = purchase_proposal
+                .shipping_address
+                .as_ref()
+                .map(|address| address.country_code.as_str());
+            let ships_to_country = country_code.is_some_and(|country_code| {
+                self.country_codes
+                    .iter()
+                    .any(|code| code.eq_ignore_ascii_case(country_code))
+            });
+            if !ships_to_country {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl Action {
+    fn name(&self) -> &str {
+        match self {
+            Action::Hide { name } | Action::Move { name, .. } | Action::Rename { name, .. } => name,
+        }
+    }
+
+    fn operation(&self, payment_method_id: ID) -> Operation {
+        let mut operation = Operation {
+            hide: None,
+            r#move: None,
+            rename: None,
+        };
+        match self {
+            Action::Hide { .. } => operation.hide = Some(HideOperation { payment_method_id }),
+            Action::Move { index, .. } => {
+                operation.r#move = Some(MoveOperation {
+                    payment_method_id,
+                    index: *index,
+                })
+            }
+            Action::Rename { to, .. } => {
+                operation.rename = Some(RenameOperation {
+                    payment_method_id,
+                    name: to.clone(),
+                })
+            }
+        }
+        operation
+    }
+}
+
+/// Two rules targeting the same payment method. The earlier rule wins.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conflict {
+    pub payment_method_id: ID,
+    /// The index of the rule that was applied.
+    pub applied: usize,
+    /// The index of the rule that was skipped.
+    pub skipped: usize,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "rules {} and {} both target payment method {}, rule {} is skipped",
+            self.applied, self.skipped, self.payment_method_id, self.skipped
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Evaluation {
+    pub operations: Vec<Operation>,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Evaluates `rules` in order, applying the first rule that targets each payment method.
+pub fn evaluate(rules: &[Rule], input: &Input) -> Result<Evaluation, money::MoneyError> {
+    let mut evaluation = Evaluation::default();
+    // The rule that targeted each payment method, by payment method id.
+    let mut targeted: Vec<(&ID, usize)> = Vec::new();
+
+    for (index, rule) in rules.iter().enumerate() {
+        if !rule.when.are_met(&input.purchase_proposal)? {
+            continue;
+        }
+        let payment_methods = input
+            .payment_methods
+            .iter()
+            .filter(|payment_method| matches_pattern(rule.action.name(), &payment_method.name));
+        for payment_method in payment_methods {
+            match targeted.iter().find(|(id, _)| **id == payment_method.id) {
+                Some((_, applied)) => evaluation.conflicts.push(Conflict {
+                    payment_method_id: payment_method.id.clone(),
+                    applied: *applied,
+                    skipped: index,
+                }),
+                None => {
+                    targeted.push((&payment_method.id, index));
+                    evaluation
+                        .operations
+                        .push(rule.action.operation(payment_method.id.clone()));
+                }
+            }
+        }
+    }
+
+    Ok(evaluation)
+}
+
+/// The sum of price times quantity of every line, or `None` if a line isn't in `currency_code`.
+fn total_price(
+    purchase_proposal: &PurchaseProposal,
+    currency_code: CurrencyCode,
+) -> Result<Option<money::Money>, money::MoneyError> {
+    let mut total = money::Money::zero(currency_code);
+    for line in &purchase_proposal.merchandise_lines {
+        if line.price.currency.parse::<CurrencyCode>().ok() != Some(currency_code) {
+            return Ok(None);
+        }
+        let subunits =
+            i64::try_from(line.price.subunits).map_err(|_| money::MoneyError::Overflow)?;
+        let line_total = money::Money::from_subunits(subunits, currency_code)
+            .checked_mul(Decimal::from(line.quantity))?;
+        total = total.checked_add(&line_total)?;
+    }
+    Ok(Some(total))
+}
+
+/// Case-insensitive match of `name` against `pattern`, where `*` matches any characters.
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let name = name.to_lowercase();
+    let mut parts = pattern.split('*');
+    // `split` always yields at least one part.
+    let first = parts.next().unwrap_or_default();
+    let mut rest = match name.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let last = match parts.pop() {
+        Some(last) => last,
+        // No `*`, the whole name must match.
+        None => return rest.is_empty(),
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(position) => rest = &rest[position + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("Cash on Delivery", "cash on delivery"));
+        assert!(!matches_pattern("Cash", "Cash on Delivery"));
+        assert!(matches_pattern("Cash*", "Cash on Delivery"));
+        assert!(matches_pattern("*delivery", "Cash on Delivery"));
+        assert!(matches_pattern("*on*", "Cash on Delivery"));
+        assert!(matches_pattern("c*h*y", "Cash on Delivery"));
+        assert!(!matches_pattern("*pay*", "Cash on Delivery"));
+        assert!(matches_pattern("*", "Auth.net"));
+        // The prefix and suffix can't overlap.
+        assert!(!matches_pattern("ab*ba", "aba"));
+    }
+
+    #[test]
+    fn test_rules_are_parsed() {
+        let rules: Vec<Rule> = serde_json::from_str(
+            r#"[
+                {"when": {"totalAbove": {"amount": "100.00", "currencyCode": "CAD"}, "countryCodes": ["CA"]}, "hide": {"name": "Cash*"}},
+                {"move": {"name": "Shopify payments", "index": 0}},
+                {"rename": {"name": "Auth.net", "to": "Credit card"}}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            rules[0].when.total_above,
+            Some(money::Money::parse("100", CurrencyCode::Cad).unwrap())
+        );
+        assert_eq!(rules[0].when.country_codes, vec!["CA"]);
+        assert_eq!(
+            rules[0].action,
+            Action::Hide {
+                name: "Cash*".to_string()
+            }
+        );
+        assert_eq!(rules[1].when, Conditions::default());
+        assert_eq!(
+            rules[2].action,
+            Action::Rename {
+                name: "Auth.net".to_string(),
+                to: "Credit card".to_string()
+            }
+        );
+    }
+}
@@ -14,12 +14,18 @@ pub struct Config {
 // Use the following container attribute if fields need to be camel cased.
 // #[serde(rename_all = "camelCase")]
 pub struct Input {
+    // The default script leaves the payment methods unchanged, so it reads
+    // neither field.
+    #[allow(dead_code)]
     pub purchase_proposal: PurchaseProposal,
+    #[allow(dead_code)]
     pub payment_methods: Vec<PaymentMethod>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct PurchaseProposal {
+    // Only read by scripts that filter on the delivery strategy.
+    #[allow(dead_code)]
     pub delivery_lines: Vec<DeliveryLine>,
 }
 
@@ -32,11 +38,13 @@ pub struct PaymentMethod {
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct DeliveryLine {
+    #[allow(dead_code)]
     pub strategy: Option<Strategy>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct Strategy {
+    #[allow(dead_code)]
     pub carrier_identifier: Option<String>,
 }
 
@@ -1,28 +1,24 @@
-use serde::Serialize;
-
 /*
  * This script provides a basic example of logging, deserialize input and
  * configuration values, and deserialize output (that leaves payment methods unchanged).
  */
 
 mod api;
+#[cfg(test)]
+mod properties;
 use api::*;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn main() -> std::process::ExitCode {
     // use stderr to print output
     eprintln!("Hello World");
 
     // read from stdin and write to stdout
-    let payload: Payload = rmp_serde::decode::from_read(std::io::stdin())?;
-    let mut out = std::io::stdout();
-    let mut serializer = rmp_serde::Serializer::new(&mut out).with_struct_map();
-    script(payload)?.serialize(&mut serializer)?;
-    Ok(())
+    shopify_function_core::run(script)
 }
 
-fn script(payload: Payload) -> Result<Output, Box<dyn std::error::Error>> {
+pub(crate) fn script(payload: Payload) -> Result<Output, Box<dyn std::error::Error>> {
     let (_input, _config) = (payload.input, payload.configuration);
-    return Ok(build_result(vec![]));
+    Ok(build_result(vec![]))
 }
 
 fn build_result(payment_methods_to_remove: Vec<PaymentMethod>) -> Output {
@@ -42,6 +38,8 @@ fn build_result(payment_methods_to_remove: Vec<PaymentMethod>) -> Output {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use shopify_function_core::fixtures;
+
     fn default_payload() -> Payload {
         Payload {
             input: Input {
@@ -84,4 +82,9 @@ mod tests {
         assert_eq!(output.filter_response.hidden_methods.len(), 0);
         assert_eq!(output.sort_response.proposed_order.len(), 0);
     }
+
+    #[test]
+    fn test_fixtures() {
+        fixtures::check("tests/fixtures", script);
+    }
 }
@@ -0,0 +1,50 @@
+//! Properties of the script on random payment methods.
+
+use proptest::prelude::*;
+use proptest::strategy::Strategy;
+use serde_json::json;
+
+use super::*;
+
+const NAMES: [&str; 3] = ["Shopify payments", "Auth.net", "Cash on Delivery"];
+
+fn payload() -> impl Strategy<Value = Payload> {
+    let payment_method = (
+        0..5u64,
+        prop::sample::select(&NAMES[..]),
+        prop::sample::subsequence(vec!["Visa", "Mastercard", "Amex"], 0..=3),
+    )
+        .prop_map(|(id, name, cards)| json!({ "id": id, "name": name, "cards": cards }));
+    (
+        prop::collection::vec(prop::option::of("[a-z]{1,8}"), 0..3),
+        prop::collection::vec(payment_method, 0..5),
+    )
+        .prop_map(|(carrier_identifiers, payment_methods)| {
+            let delivery_lines: Vec<_> = carrier_identifiers
+                .into_iter()
+                .map(|carrier_identifier| {
+                    json!({ "strategy": { "carrier_identifier": carrier_identifier } })
+                })
+                .collect();
+            serde_json::from_value(json!({
+                "input": {
+                    "purchase_proposal": { "delivery_lines": delivery_lines },
+                    "payment_methods": payment_methods,
+                },
+                "configuration": {},
+            }))
+            .expect("the payload deserializes")
+        })
+}
+
+proptest! {
+    #[test]
+    fn test_leaves_payment_methods_unchanged(payload in payload()) {
+        let output = script(payload).unwrap();
+
+        prop_assert!(serde_json::to_value(&output).is_ok());
+        prop_assert!(output.sort_response.proposed_order.is_empty());
+        prop_assert!(output.filter_response.hidden_methods.is_empty());
+        prop_assert!(output.rename_response.rename_proposals.is_empty());
+    }
+}
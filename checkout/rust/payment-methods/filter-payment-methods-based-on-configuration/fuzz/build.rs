@@ -0,0 +1,4 @@
+fn main() {
+    shopify_function_codegen::Codegen::default()
+        .write_config_types("../script.config.yml", "config.rs");
+}
@@ -6,12 +6,11 @@ pub struct Payload {
     pub configuration: Config,
 }
 
-// configuration fields match what's defined in script.config.yml.
-#[derive(Clone, Debug, Deserialize)]
-pub struct Config {
-    pub payment_method_name: String,
-    pub threshold: String,
+// configuration fields are generated from what's defined in script.config.yml.
+mod config {
+    include!(concat!(env!("OUT_DIR"), "/config.rs"));
 }
+pub use config::*;
 
 #[derive(Clone, Debug, Deserialize)]
 // Use the following container attribute if fields need to be camel cased.
@@ -36,7 +35,7 @@ pub struct PaymentMethod {
 #[derive(Clone, Debug, Deserialize)]
 pub struct MerchandiseLine {
     pub quantity: u32,
-    pub price: Money
+    pub price: Money,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -1,46 +1,90 @@
 /*
  * This script filters payment methods when both of the following conditions are met:
  *   - The payment method name matches the `payment_method_name` field from the configuration.
- *   - The total price of the checkout (`purchase_proposal`) is greater than the `threshold` field
- *     from the configuration in CAD.
+ *   - The total price of the checkout (`purchase_proposal`) compares to the `threshold` field
+ *     from the configuration, in the configured `currency`, as set by `comparison` (`>`, `>=`,
+ *     `<` or `<=` by default).
  */
 
-use serde::Serialize;
+use shopify_function_core::{money, CurrencyCode, Decimal};
 
 mod api;
+#[cfg(test)]
+mod properties;
 use api::*;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn main() -> std::process::ExitCode {
     // use stderr to print output
     eprintln!("Hello World");
 
     // read from stdin and write to stdout
-    let payload: Payload = rmp_serde::decode::from_read(std::io::stdin())?;
-    let mut out = std::io::stdout();
-    let mut serializer = rmp_serde::Serializer::new(&mut out).with_struct_map();
-    script(payload)?.serialize(&mut serializer)?;
-    Ok(())
+    shopify_function_core::run(script)
+}
+
+impl Comparison {
+    fn matches(self, total: &money::Money, threshold: &money::Money) -> bool {
+        match self {
+            Comparison::GreaterThan => total > threshold,
+            Comparison::GreaterThanOrEqual => total >= threshold,
+            Comparison::LessThan => total < threshold,
+            Comparison::LessThanOrEqual => total <= threshold,
+        }
+    }
 }
 
-fn script(payload: Payload) -> Result<Output, Box<dyn std::error::Error>> {
+pub(crate) fn script(payload: Payload) -> Result<Output, Box<dyn std::error::Error>> {
     let (input, config) = (payload.input, payload.configuration);
 
-    // for now, all configuration values are strings, and casting is needed
-    let threshold = config.threshold.parse::<f32>().unwrap();
-    let payment_method_name  = config.payment_method_name;
+    let currency_code = currency_code(&config.currency)?;
+    let threshold = money::Money::new(config.threshold, currency_code);
+    let payment_method_name = config.payment_method_name;
 
-    // this example only works for some currencies such as CAD
-    let total_price = (input.purchase_proposal.merchandise_lines.iter().fold(
-        0, |sum, line| sum + line.price.subunits) as f32)/100.00;
+    let total_price = match total_price(&input.purchase_proposal, currency_code)? {
+        Some(total_price) => total_price,
+        None => {
+            eprintln!(
+                "checkout isn't in {}, no payment method is hidden",
+                currency_code
+            );
+            return Ok(build_result(vec![]));
+        }
+    };
 
     let mut hidden_methods = vec![];
 
-    if total_price <= threshold {
-        hidden_methods = input.payment_methods.into_iter()
-          .filter(|payment_method| payment_method.name==payment_method_name).collect();
+    if config.comparison.matches(&total_price, &threshold) {
+        hidden_methods = input
+            .payment_methods
+            .into_iter()
+            .filter(|payment_method| payment_method.name == payment_method_name)
+            .collect();
     }
 
-    return Ok(build_result(hidden_methods));
+    Ok(build_result(hidden_methods))
+}
+
+fn currency_code(code: &str) -> Result<CurrencyCode, Box<dyn std::error::Error>> {
+    match code.trim().to_uppercase().parse()? {
+        CurrencyCode::Other => Err(format!("unknown currency `{}`", code).into()),
+        currency_code => Ok(currency_code),
+    }
+}
+
+/// The sum of price times quantity of every line, or `None` if a line isn't in `currency_code`.
+fn total_price(
+    purchase_proposal: &PurchaseProposal,
+    currency_code: CurrencyCode,
+) -> Result<Option<money::Money>, Box<dyn std::error::Error>> {
+    let mut total = money::Money::zero(currency_code);
+    for line in &purchase_proposal.merchandise_lines {
+        if line.price.currency.parse::<CurrencyCode>().ok() != Some(currency_code) {
+            return Ok(None);
+        }
+        let line_total = money::Money::from_subunits(line.price.subunits.into(), currency_code)
+            .checked_mul(Decimal::from(line.quantity))?;
+        total = total.checked_add(&line_total)?;
+    }
+    Ok(Some(total))
 }
 
 fn build_result(payment_methods_to_remove: Vec<PaymentMethod>) -> Output {
@@ -60,6 +104,8 @@ fn build_result(payment_methods_to_remove: Vec<PaymentMethod>) -> Output {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use shopify_function_core::fixtures;
+
     fn default_payload() -> Payload {
         Payload {
             input: Input {
@@ -70,14 +116,14 @@ mod tests {
                             price: Money {
                                 subunits: 5000,
                                 currency: "CAD".to_string(),
-                            }
+                            },
                         },
                         MerchandiseLine {
                             quantity: 3,
                             price: Money {
                                 subunits: 1000,
                                 currency: "CAD".to_string(),
-                            }
+                            },
                         },
                     ],
                 },
@@ -105,7 +151,9 @@ mod tests {
             },
             configuration: Config {
                 payment_method_name: "Shopify payments".to_string(),
-                threshold: "30.05".to_string(),
+                threshold: "30.05".parse().unwrap(),
+                currency: "CAD".to_string(),
+                comparison: Comparison::LessThanOrEqual,
             },
         }
     }
@@ -134,11 +182,170 @@ mod tests {
     #[test]
     fn test_returns_1_hidden_method() {
         let mut payload = default_payload();
-        payload.configuration.threshold = "300.01".to_string();
+        payload.configuration.threshold = "300.01".parse().unwrap();
         let output = script(payload).unwrap();
 
         assert_eq!(output.filter_response.hidden_methods[0].name, "Shopify payments".to_string());
         assert_eq!(output.rename_response.rename_proposals.len(), 0);
         assert_eq!(output.sort_response.proposed_order.len(), 0);
     }
+
+    fn hidden_method_names(payload: Payload) -> Vec<String> {
+        script(payload)
+            .unwrap()
+            .filter_response
+            .hidden_methods
+            .into_iter()
+            .map(|payment_method| payment_method.name)
+            .collect()
+    }
+
+    #[test]
+    fn test_total_counts_every_unit() {
+        let mut payload = default_payload();
+        payload.input.purchase_proposal.merchandise_lines = vec![MerchandiseLine {
+            quantity: 10,
+            price: Money {
+                subunits: 500,
+                currency: "CAD".to_string(),
+            },
+        }];
+        payload.configuration.threshold = "40.00".parse().unwrap();
+        payload.configuration.comparison = Comparison::GreaterThan;
+
+        assert_eq!(hidden_method_names(payload), vec!["Shopify payments"]);
+    }
+
+    #[test]
+    fn test_total_does_not_overflow_u32() {
+        let mut payload = default_payload();
+        payload.input.purchase_proposal.merchandise_lines = vec![MerchandiseLine {
+            quantity: u32::MAX,
+            price: Money {
+                subunits: u32::MAX,
+                currency: "CAD".to_string(),
+            },
+        }];
+        payload.configuration.threshold = "1000000.00".parse().unwrap();
+        payload.configuration.comparison = Comparison::GreaterThan;
+
+        assert_eq!(hidden_method_names(payload), vec!["Shopify payments"]);
+    }
+
+    #[test]
+    fn test_greater_than_excludes_the_threshold() {
+        // 2 x 50.00 + 3 x 10.00
+        let mut payload = default_payload();
+        payload.configuration.threshold = "130.00".parse().unwrap();
+        payload.configuration.comparison = Comparison::GreaterThan;
+
+        assert!(hidden_method_names(payload).is_empty());
+    }
+
+    #[test]
+    fn test_greater_than_or_equal_includes_the_threshold() {
+        let mut payload = default_payload();
+        payload.configuration.threshold = "130.00".parse().unwrap();
+        payload.configuration.comparison = Comparison::GreaterThanOrEqual;
+
+        assert_eq!(hidden_method_names(payload), vec!["Shopify payments"]);
+    }
+
+    #[test]
+    fn test_less_than() {
+        let mut payload = default_payload();
+        payload.configuration.comparison = Comparison::LessThan;
+        payload.configuration.threshold = "130.01".parse().unwrap();
+        assert_eq!(hidden_method_names(payload), vec!["Shopify payments"]);
+
+        let mut payload = default_payload();
+        payload.configuration.comparison = Comparison::LessThan;
+        payload.configuration.threshold = "130.00".parse().unwrap();
+        assert!(hidden_method_names(payload).is_empty());
+    }
+
+    #[test]
+    fn test_less_than_or_equal_is_the_default() {
+        let mut payload = default_payload();
+        payload.configuration.threshold = "130.00".parse().unwrap();
+        assert_eq!(hidden_method_names(payload), vec!["Shopify payments"]);
+
+        let mut payload = default_payload();
+        payload.configuration.threshold = "129.99".parse().unwrap();
+        assert!(hidden_method_names(payload).is_empty());
+    }
+
+    fn config(values: &[(&str, &str)]) -> Result<Config, rmp_serde::decode::Error> {
+        let values: std::collections::HashMap<_, _> = values.iter().copied().collect();
+        rmp_serde::from_slice(&rmp_serde::to_vec(&values).unwrap())
+    }
+
+    #[test]
+    fn test_config_values_are_coerced() {
+        let config = config(&[
+            ("payment_method_name", "Shopify payments"),
+            ("threshold", "30.05"),
+            ("comparison", ">="),
+        ])
+        .unwrap();
+
+        assert_eq!(config.threshold, "30.05".parse().unwrap());
+        assert_eq!(config.currency, "CAD");
+        assert_eq!(config.comparison, Comparison::GreaterThanOrEqual);
+
+        let config = self::config(&[("payment_method_name", "Cash"), ("threshold", "1")]).unwrap();
+        assert_eq!(config.comparison, Comparison::LessThanOrEqual);
+    }
+
+    #[test]
+    fn test_invalid_config_is_an_error() {
+        let error = config(&[
+            ("payment_method_name", "Shopify payments"),
+            ("threshold", "30.05"),
+            ("comparison", "=>"),
+        ])
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("configuration `comparison` is \"=>\", expected one of `>`, `>=`, `<`, `<=`"));
+
+        let error =
+            config(&[("payment_method_name", "Cash"), ("threshold", "thirty")]).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("configuration `threshold` is \"thirty\", expected a decimal number"));
+
+        let error = config(&[("threshold", "30.05")]).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("configuration `payment_method_name` is required"));
+    }
+
+    #[test]
+    fn test_compares_in_the_threshold_currency() {
+        // JPY has no minor units, so 2 x 5000 + 3 x 1000 is 13000 yen.
+        let mut payload = default_payload();
+        for line in &mut payload.input.purchase_proposal.merchandise_lines {
+            line.price.currency = "JPY".to_string();
+        }
+        payload.configuration.currency = "JPY".to_string();
+        payload.configuration.threshold = "12999".parse().unwrap();
+        payload.configuration.comparison = Comparison::GreaterThan;
+
+        assert_eq!(hidden_method_names(payload), vec!["Shopify payments"]);
+    }
+
+    #[test]
+    fn test_other_currencies_are_not_compared() {
+        let mut payload = default_payload();
+        payload.configuration.currency = "USD".to_string();
+        payload.configuration.threshold = "1000.00".parse().unwrap();
+
+        assert!(hidden_method_names(payload).is_empty());
+    }
+
+    #[test]
+    fn test_fixtures() {
+        fixtures::check("tests/fixtures", script);
+    }
 }
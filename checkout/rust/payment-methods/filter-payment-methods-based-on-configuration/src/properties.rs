@@ -0,0 +1,83 @@
+//! Properties of the script on random checkouts and configurations.
+
+use proptest::prelude::*;
+use serde_json::json;
+use shopify_function_core::strategies;
+
+use super::*;
+
+const NAMES: [&str; 3] = ["Shopify payments", "Auth.net", "Cash on Delivery"];
+const CURRENCIES: [&str; 3] = ["CAD", "USD", "JPY"];
+
+fn payload() -> impl Strategy<Value = Payload> {
+    let line = (
+        0..10u32,
+        0..100_000u32,
+        prop::sample::select(&CURRENCIES[..]),
+    )
+        .prop_map(|(quantity, subunits, currency)| {
+            json!({ "quantity": quantity, "price": { "subunits": subunits, "currency": currency } })
+        });
+    let payment_method = (0..5u64, prop::sample::select(&NAMES[..]))
+        .prop_map(|(id, name)| json!({ "id": id, "name": name, "cards": [] }));
+    let configuration = (
+        prop::sample::select(&NAMES[..]),
+        strategies::amount(),
+        prop::sample::select(&CURRENCIES[..]),
+        prop::sample::select(vec![">", ">=", "<", "<="]),
+    )
+        .prop_map(|(payment_method_name, threshold, currency, comparison)| {
+            json!({
+                "payment_method_name": payment_method_name,
+                "threshold": threshold,
+                "currency": currency,
+                "comparison": comparison,
+            })
+        });
+    (
+        prop::collection::vec(line, 0..4),
+        prop::collection::vec(payment_method, 0..5),
+        configuration,
+    )
+        .prop_map(|(merchandise_lines, payment_methods, configuration)| {
+            serde_json::from_value(json!({
+                "input": {
+                    "purchase_proposal": { "merchandise_lines": merchandise_lines },
+                    "payment_methods": payment_methods,
+                },
+                "configuration": configuration,
+            }))
+            .expect("the payload deserializes")
+        })
+}
+
+proptest! {
+    #[test]
+    fn test_hides_only_the_configured_payment_method(payload in payload()) {
+        let payment_method_name = payload.configuration.payment_method_name.clone();
+        let output = script(payload).unwrap();
+
+        prop_assert!(serde_json::to_value(&output).is_ok());
+        for payment_method in &output.filter_response.hidden_methods {
+            prop_assert_eq!(&payment_method.name, &payment_method_name);
+        }
+        prop_assert!(output.sort_response.proposed_order.is_empty());
+        prop_assert!(output.rename_response.rename_proposals.is_empty());
+    }
+
+    #[test]
+    fn test_other_currencies_are_not_compared(payload in payload()) {
+        let currency = payload.configuration.currency.clone();
+        let in_other_currency = payload
+            .input
+            .purchase_proposal
+            .merchandise_lines
+            .iter()
+            .any(|line| line.price.currency != currency);
+        let output = script(payload).unwrap();
+
+        if in_other_currency {
+            prop_assert!(output.filter_response.hidden_methods.is_empty());
+        }
+    }
+}
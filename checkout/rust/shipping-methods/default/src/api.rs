@@ -14,12 +14,16 @@ pub struct Config {
 // Use the following container attribute if fields need to be camel cased.
 // #[serde(rename_all = "camelCase")]
 pub struct Input {
+    // The default script only looks at the shipping methods.
+    #[allow(dead_code)]
     pub purchase_proposal: PurchaseProposal,
     pub shipping_methods: Vec<ShippingMethod>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct PurchaseProposal {
+    // Only read by scripts that pick a method by delivery strategy.
+    #[allow(dead_code)]
     pub delivery_lines: Vec<DeliveryLine>,
 }
  
@@ -41,11 +45,13 @@ pub struct ShippingMethod {
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct DeliveryLine {
+    #[allow(dead_code)]
     pub strategy: Option<Strategy>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct Strategy {
+    #[allow(dead_code)]
     pub carrier_identifier: Option<String>,
 }
 
@@ -1,34 +1,31 @@
-use serde::Serialize;
-
 /*
  * This script provides a basic example of logging, deserialize input and
  * configuration values, and deserialize output (that leaves payment methods unchanged).
  */
 
 mod api;
+#[cfg(test)]
+mod properties;
 use api::*;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn main() -> std::process::ExitCode {
     // use stderr to print output
     eprintln!("Hello World1");
 
     // read from stdin and write to stdout
-    let payload: Payload = serde_json::from_reader(std::io::BufReader::new(std::io::stdin()))?;
-    let mut out = std::io::stdout();
-    let mut serializer = serde_json::Serializer::new(&mut out);
-    script(payload)?.serialize(&mut serializer)?;
-    Ok(())
+    shopify_function_core::run(script)
 }
 
-fn script(payload: Payload) -> Result<Output, Box<dyn std::error::Error>> {
+pub(crate) fn script(payload: Payload) -> Result<Output, Box<dyn std::error::Error>> {
     let (input, _config) = (payload.input, payload.configuration);
-    let rename_proposals = vec![
+    // checkouts without shipping methods have nothing to rename
+    let rename_proposals = input.shipping_methods.first().map(|shipping_method| {
         RenameProposal {
-            shipping_method: input.shipping_methods[0].id.to_string(),
+            shipping_method: shipping_method.id.to_string(),
             name: "My Renamed Shipping Method".to_string(),
             renamed: true
         }
-    ];
+    }).into_iter().collect();
 
     Ok(Output {
         sort_response: SortResponse {
@@ -38,7 +35,7 @@ fn script(payload: Payload) -> Result<Output, Box<dyn std::error::Error>> {
             hidden_methods: vec![],
         },
         rename_response: RenameResponse {
-            rename_proposals: rename_proposals,
+            rename_proposals,
         },
     })
 }
@@ -46,6 +43,8 @@ fn script(payload: Payload) -> Result<Output, Box<dyn std::error::Error>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use shopify_function_core::fixtures;
+
     fn default_payload() -> Payload {
         Payload {
             input: Input {
@@ -93,4 +92,18 @@ mod tests {
 
         assert_eq!(output.rename_response.rename_proposals[0].name, "My Renamed Shipping Method");
     }
+
+    #[test]
+    fn test_renames_nothing_without_shipping_methods() {
+        let mut payload = default_payload();
+        payload.input.shipping_methods = vec![];
+        let output = script(payload).unwrap();
+
+        assert_eq!(output.rename_response.rename_proposals.len(), 0);
+    }
+
+    #[test]
+    fn test_fixtures() {
+        fixtures::check("tests/fixtures", script);
+    }
 }
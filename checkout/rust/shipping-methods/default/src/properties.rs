@@ -0,0 +1,50 @@
+//! Properties of the script on random shipping methods.
+
+use proptest::prelude::*;
+use proptest::strategy::Strategy;
+use serde_json::json;
+
+use super::*;
+
+fn payload() -> impl Strategy<Value = Payload> {
+    let shipping_method = (any::<u64>(), "[A-Za-z ]{0,12}", any::<bool>(), 0..10_000u64).prop_map(
+        |(id, title, phone_required, subunits)| {
+            json!({
+                "id": id,
+                "title": title,
+                "code": title.to_lowercase(),
+                "phone_required": phone_required,
+                "amount": { "currency": "USD", "subunits": subunits },
+                "markup": null,
+            })
+        },
+    );
+    prop::collection::vec(shipping_method, 0..4).prop_map(|shipping_methods| {
+        serde_json::from_value(json!({
+            "input": {
+                "purchase_proposal": { "delivery_lines": [] },
+                "shipping_methods": shipping_methods,
+            },
+            "configuration": {},
+        }))
+        .expect("the payload deserializes")
+    })
+}
+
+proptest! {
+    #[test]
+    fn test_renames_the_first_shipping_method(payload in payload()) {
+        let first_id = payload.input.shipping_methods.first().map(|method| method.id.to_string());
+        let output = script(payload).unwrap();
+
+        prop_assert!(serde_json::to_value(&output).is_ok());
+        let renamed: Vec<String> = output
+            .rename_response
+            .rename_proposals
+            .into_iter()
+            .map(|proposal| proposal.shipping_method)
+            .collect();
+        prop_assert_eq!(renamed, first_id.into_iter().collect::<Vec<_>>());
+        prop_assert!(output.filter_response.hidden_methods.is_empty());
+    }
+}
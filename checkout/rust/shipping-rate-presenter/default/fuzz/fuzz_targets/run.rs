@@ -0,0 +1,15 @@
+//! Feeds arbitrary bytes to the script's entry point, see `harness::fuzz`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use shopify_function_core::harness;
+use shopify_function_core::run::Format;
+
+#[allow(dead_code)]
+#[path = "../../src/main.rs"]
+mod function;
+
+fuzz_target!(|data: &[u8]| {
+    harness::fuzz(Format::Json, data, |payload| function::script(&payload))
+});
@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Debug, Deserialize)]
 pub struct Payload {
     pub input: Input,
+    // The default script has nothing to configure.
+    #[allow(dead_code)]
     pub configuration: Config,
 }
 
@@ -38,11 +40,6 @@ pub struct ShippingRate {
     pub markup: Money
 }
 
-#[derive(Clone, Debug, Deserialize)]
-pub struct Strategy {
-    pub carrier_identifier: Option<String>,
-}
-
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Money {
     pub subunits: u64,
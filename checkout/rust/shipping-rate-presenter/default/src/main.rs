@@ -1,5 +1,3 @@
-use serde::Serialize;
-
 /*
  * This script provides a basic example of logging, deserialize input and
  * configuration values, and deserializing output. 
@@ -8,25 +6,23 @@ use serde::Serialize;
  */
 
 mod api;
+#[cfg(test)]
+mod properties;
 use api::*;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn main() -> std::process::ExitCode {
     // use stderr to print output
     eprintln!("Hello World");
 
     // read from stdin and write to stdout
-    let payload: Payload = serde_json::from_reader(std::io::BufReader::new(std::io::stdin()))?;
-    let mut out = std::io::stdout();
-    let mut serializer = serde_json::Serializer::new(&mut out);
-    script(&payload)?.serialize(&mut serializer)?;
-    Ok(())
+    shopify_function_core::run(|payload: Payload| script(&payload))
 }
 
-fn script(payload: &Payload) -> Result<Output, Box<dyn std::error::Error>> {
+pub(crate) fn script(payload: &Payload) -> Result<Output, Box<dyn std::error::Error>> {
     let customer_email = &payload.input.purchase_proposal.buyer_identity.email;
     let shipping_rates = &payload.input.shipping_rates;
 
-    return Ok(
+    Ok(
         Output {
             rename_proposals: shipping_rates.iter().map(|rate| {
                 RenameProposal {
@@ -37,12 +33,14 @@ fn script(payload: &Payload) -> Result<Output, Box<dyn std::error::Error>> {
             proposed_order: None,
             hidden_rate_ids: vec![],
         }
-    );
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use shopify_function_core::fixtures;
+
     fn default_payload() -> Payload {
         Payload {
             input: Input {
@@ -109,4 +107,9 @@ mod tests {
         assert_eq!(output.rename_proposals.len(), 3);
         assert!(output.rename_proposals.iter().all(|proposal| proposal.name == payload.input.purchase_proposal.buyer_identity.email));
     }
+
+    #[test]
+    fn test_fixtures() {
+        fixtures::check("tests/fixtures", |payload: Payload| script(&payload));
+    }
 }
@@ -0,0 +1,51 @@
+//! Properties of the script on random shipping rates.
+
+use proptest::prelude::*;
+use serde_json::json;
+
+use super::*;
+
+fn payload() -> impl Strategy<Value = Payload> {
+    let money = |subunits| json!({ "subunits": subunits, "currency": "USD" });
+    let shipping_rate = (any::<u64>(), "[A-Za-z ]{0,12}", any::<bool>(), 0..10_000u64).prop_map(
+        move |(id, title, phone_required, subunits)| {
+            json!({
+                "id": id,
+                "title": title,
+                "code": title.to_lowercase(),
+                "amount": money(subunits),
+                "phone_required": phone_required,
+                "markup": money(0),
+            })
+        },
+    );
+    (
+        "[a-z.]{0,8}@[a-z]{1,8}\\.com",
+        prop::collection::vec(shipping_rate, 0..4),
+    )
+        .prop_map(|(email, shipping_rates)| {
+            serde_json::from_value(json!({
+                "input": {
+                    "purchase_proposal": { "buyer_identity": { "email": email } },
+                    "shipping_rates": shipping_rates,
+                },
+                "configuration": {},
+            }))
+            .expect("the payload deserializes")
+        })
+}
+
+proptest! {
+    #[test]
+    fn test_renames_every_rate_to_the_email(payload in payload()) {
+        let output = script(&payload).unwrap();
+
+        prop_assert!(serde_json::to_value(&output).is_ok());
+        let rate_ids: Vec<u64> = payload.input.shipping_rates.iter().map(|rate| rate.id).collect();
+        let renamed_ids: Vec<u64> = output.rename_proposals.iter().map(|proposal| proposal.shipping_rate_id).collect();
+        prop_assert_eq!(renamed_ids, rate_ids);
+        let email = &payload.input.purchase_proposal.buyer_identity.email;
+        prop_assert!(output.rename_proposals.iter().all(|proposal| &proposal.name == email));
+        prop_assert!(output.hidden_rate_ids.is_empty());
+    }
+}
@@ -0,0 +1,40 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    Parse(String),
+    UnknownType(String),
+    UnknownField { type_name: String, field: String },
+    UnknownFragment(String),
+    MissingSelection { type_name: String, field: String },
+    Unsupported(String),
+    ScriptConfig(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(message) => write!(f, "unable to parse GraphQL: {}", message),
+            Error::UnknownType(name) => write!(f, "type `{}` is not defined in the schema", name),
+            Error::UnknownField { type_name, field } => {
+                write!(
+                    f,
+                    "field `{}` is not defined on type `{}`",
+                    field, type_name
+                )
+            }
+            Error::UnknownFragment(name) => write!(f, "fragment `{}` is not defined", name),
+            Error::MissingSelection { type_name, field } => write!(
+                f,
+                "field `{}` on type `{}` needs a selection of subfields",
+                field, type_name
+            ),
+            Error::Unsupported(message) => write!(f, "unsupported: {}", message),
+            Error::ScriptConfig(message) => {
+                write!(f, "invalid script.config.yml: {}", message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
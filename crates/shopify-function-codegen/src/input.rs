@@ -0,0 +1,258 @@
+use std::collections::{BTreeSet, HashMap};
+
+use graphql_parser::query::{self, Definition, OperationDefinition, Selection, SelectionSet};
+use graphql_parser::schema::{Field as FieldDefinition, TypeDefinition};
+
+use crate::naming;
+use crate::render::{Field, Item};
+use crate::schema::{self, Schema};
+use crate::Error;
+
+/// A field of a selection set, merged across fragments.
+struct Selected<'q> {
+    key: String,
+    field: Option<FieldDefinition<'q, &'q str>>,
+    optional: bool,
+    selections: Vec<&'q SelectionSet<'q, &'q str>>,
+}
+
+pub struct InputGenerator<'a, 'q> {
+    schema: &'a Schema<'q>,
+    fragments: HashMap<&'q str, &'q query::FragmentDefinition<'q, &'q str>>,
+    structs: Vec<Option<(String, Item)>>,
+    names: HashMap<String, usize>,
+    enums: Vec<&'q str>,
+    pub scalars: BTreeSet<&'q str>,
+}
+
+impl<'a, 'q> InputGenerator<'a, 'q> {
+    pub fn new(schema: &'a Schema<'q>) -> Self {
+        InputGenerator {
+            schema,
+            fragments: HashMap::new(),
+            structs: Vec::new(),
+            names: HashMap::new(),
+            enums: Vec::new(),
+            scalars: BTreeSet::new(),
+        }
+    }
+
+    pub fn generate(
+        mut self,
+        document: &'q query::Document<'q, &'q str>,
+    ) -> Result<(Vec<Item>, BTreeSet<&'q str>), Error> {
+        let mut operation = None;
+        for definition in &document.definitions {
+            match definition {
+                Definition::Fragment(fragment) => {
+                    self.fragments.insert(fragment.name, fragment);
+                }
+                Definition::Operation(OperationDefinition::Query(query)) => {
+                    operation = operation.or(Some(&query.selection_set));
+                }
+                Definition::Operation(OperationDefinition::SelectionSet(selection_set)) => {
+                    operation = operation.or(Some(selection_set));
+                }
+                Definition::Operation(_) => {
+                    return Err(Error::Unsupported(
+                        "only query operations are supported".to_string(),
+                    ))
+                }
+            }
+        }
+        let selection_set =
+            operation.ok_or_else(|| Error::Unsupported("the document has no query".to_string()))?;
+
+        let query_type = self.schema.query_type();
+        self.selection(
+            query_type,
+            vec![selection_set],
+            vec![query_type.to_string()],
+        )?;
+
+        let mut items: Vec<Item> = self
+            .structs
+            .into_iter()
+            .flatten()
+            .map(|(_, item)| item)
+            .collect();
+        for name in self.enums {
+            items.push(self.schema.enum_item(name)?);
+        }
+        Ok((items, self.scalars))
+    }
+
+    /// Generates the struct for a selection set on `type_name`, returning its name.
+    fn selection(
+        &mut self,
+        type_name: &'q str,
+        selection_sets: Vec<&'q SelectionSet<'q, &'q str>>,
+        candidates: Vec<String>,
+    ) -> Result<String, Error> {
+        let name = self.reserve(candidates);
+        let index = self.names[&name];
+
+        let mut selected = Vec::new();
+        for selection_set in selection_sets {
+            self.collect(type_name, selection_set, false, &mut selected)?;
+        }
+
+        let mut fields = Vec::new();
+        for selected in selected {
+            fields.push(self.field(type_name, &name, selected)?);
+        }
+
+        let existing = self.structs.iter().flatten().find(|(existing_type, item)| {
+            matches!(item, Item::Struct { fields: existing, .. } if existing_type == type_name && *existing == fields)
+        });
+        if let Some((_, Item::Struct { name: existing, .. })) = existing {
+            let existing = existing.clone();
+            self.names.remove(&name);
+            return Ok(existing);
+        }
+
+        self.structs[index] = Some((
+            type_name.to_string(),
+            Item::Struct {
+                name: name.clone(),
+                doc: self.schema.description(type_name).map(str::to_string),
+                fields,
+            },
+        ));
+        Ok(name)
+    }
+
+    fn reserve(&mut self, candidates: Vec<String>) -> String {
+        let last = candidates.last().cloned().unwrap_or_default();
+        let name = candidates
+            .into_iter()
+            .find(|candidate| !self.names.contains_key(candidate))
+            .unwrap_or_else(|| {
+                (2..)
+                    .map(|n| format!("{}{}", last, n))
+                    .find(|candidate| !self.names.contains_key(candidate))
+                    .unwrap()
+            });
+        self.names.insert(name.clone(), self.structs.len());
+        self.structs.push(None);
+        name
+    }
+
+    fn collect(
+        &self,
+        type_name: &'q str,
+        selection_set: &'q SelectionSet<'q, &'q str>,
+        optional: bool,
+        selected: &mut Vec<Selected<'q>>,
+    ) -> Result<(), Error> {
+        for selection in &selection_set.items {
+            match selection {
+                Selection::Field(field) => {
+                    let key = field.alias.unwrap_or(field.name).to_string();
+                    let definition = if field.name == "__typename" {
+                        None
+                    } else {
+                        Some(self.schema.field(type_name, field.name)?.clone())
+                    };
+                    match selected.iter_mut().find(|s| s.key == key) {
+                        Some(existing) => {
+                            existing.optional &= optional;
+                            existing.selections.push(&field.selection_set);
+                        }
+                        None => selected.push(Selected {
+                            key,
+                            field: definition,
+                            optional,
+                            selections: vec![&field.selection_set],
+                        }),
+                    }
+                }
+                Selection::InlineFragment(fragment) => {
+                    let condition = match &fragment.type_condition {
+                        Some(query::TypeCondition::On(condition)) => condition,
+                        None => type_name,
+                    };
+                    let optional = optional || condition != type_name;
+                    self.collect(condition, &fragment.selection_set, optional, selected)?;
+                }
+                Selection::FragmentSpread(spread) => {
+                    let fragment = self
+                        .fragments
+                        .get(spread.fragment_name)
+                        .ok_or_else(|| Error::UnknownFragment(spread.fragment_name.to_string()))?;
+                    let query::TypeCondition::On(condition) = fragment.type_condition;
+                    let optional = optional || condition != type_name;
+                    self.collect(condition, &fragment.selection_set, optional, selected)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn field(
+        &mut self,
+        type_name: &'q str,
+        struct_name: &str,
+        selected: Selected<'q>,
+    ) -> Result<Field, Error> {
+        let name = naming::field_name(&selected.key);
+        let definition = match selected.field {
+            Some(definition) => definition,
+            None => {
+                return Ok(Field {
+                    name,
+                    key: selected.key,
+                    ty: "String".to_string(),
+                    doc: None,
+                })
+            }
+        };
+
+        let field_type = schema::named_type(&definition.field_type);
+        let inner = match self.schema.get(field_type) {
+            _ if self.schema.is_scalar(field_type) => {
+                let (ty, imported) = schema::scalar_type(field_type);
+                if imported {
+                    self.scalars.insert(field_type);
+                }
+                ty.to_string()
+            }
+            Ok(TypeDefinition::Enum(_)) => {
+                if !self.enums.contains(&field_type) {
+                    self.enums.push(field_type);
+                }
+                naming::type_name(field_type)
+            }
+            Ok(_) => {
+                let selections: Vec<_> = selected
+                    .selections
+                    .into_iter()
+                    .filter(|selection_set| !selection_set.items.is_empty())
+                    .collect();
+                if selections.is_empty() {
+                    return Err(Error::MissingSelection {
+                        type_name: type_name.to_string(),
+                        field: definition.name.to_string(),
+                    });
+                }
+                let candidates = vec![
+                    naming::type_name(field_type),
+                    format!("{}{}", struct_name, naming::type_name(&selected.key)),
+                ];
+                self.selection(field_type, selections, candidates)?
+            }
+            Err(error) => return Err(error),
+        };
+
+        let mut ty = schema::rust_type(&definition.field_type, &inner);
+        if selected.optional && !ty.starts_with("Option<") {
+            ty = format!("Option<{}>", ty);
+        }
+        Ok(Field {
+            name,
+            key: selected.key,
+            ty,
+            doc: definition.description.clone(),
+        })
+    }
+}
@@ -0,0 +1,559 @@
+//! Generates Rust types for a Shopify Function from its `schema.graphql`.
+//!
+//! Input types are generated from the function's `input.graphql` query, so the
+//! query is the single source of truth for what the function can read: one
+//! struct per selection set, holding exactly the selected fields. Unions and
+//! interfaces become a struct with the fields of every fragment as `Option`s.
+//!
+//! Output types are generated from the input type handed to the schema's result
+//! mutation (`FunctionResult`). `@oneOf` input types become enums; a member
+//! named after its type (`percentage: Percentage`) wraps that type, any other
+//! member (`productVariant: ProductVariantTarget`) has its fields inlined.
+//!
+//! Scalars, `CurrencyCode` so amounts can be turned into `Money`, and
+//! `LanguageCode` so configurations can key messages by language, are imported
+//! from `shopify_function_core::scalars`.
+//!
+//! Checkout scripts can also generate their `Config` from `script.config.yml`,
+//! with a field per configuration field typed after its `type`
+//! (`number_decimal` is a `Decimal`, `list.*` a `Vec`, fields with `choices`
+//! an enum), read from the string values with
+//! `shopify_function_core::config::Values`.
+//!
+//! From a build script:
+//!
+//! ```no_run
+//! shopify_function_codegen::Codegen::default().write_input_types(
+//!     "schema.graphql",
+//!     "input.graphql",
+//!     "input.rs",
+//! );
+//! ```
+//!
+//! and in the function:
+//!
+//! ```ignore
+//! mod input {
+//!     include!(concat!(env!("OUT_DIR"), "/input.rs"));
+//! }
+//! ```
+
+mod error;
+mod input;
+mod naming;
+mod output;
+mod render;
+mod schema;
+mod script_config;
+
+use std::path::{Path, PathBuf};
+
+pub use error::Error;
+use render::Direction;
+use schema::Schema;
+
+pub struct Codegen {
+    scalars_path: String,
+    config_path: String,
+}
+
+impl Default for Codegen {
+    fn default() -> Self {
+        Codegen {
+            scalars_path: "shopify_function_core::scalars".to_string(),
+            config_path: "shopify_function_core::config".to_string(),
+        }
+    }
+}
+
+impl Codegen {
+    /// The module the generated code imports `ID`, `Decimal`, etc. from.
+    pub fn scalars_path(mut self, path: &str) -> Self {
+        self.scalars_path = path.to_string();
+        self
+    }
+
+    /// The module the generated `Config` reads its values with.
+    pub fn config_path(mut self, path: &str) -> Self {
+        self.config_path = path.to_string();
+        self
+    }
+
+    /// Generates the types for the fields selected by `query`.
+    pub fn input_types(&self, schema: &str, query: &str) -> Result<String, Error> {
+        let schema = Schema::parse(schema)?;
+        let document = graphql_parser::parse_query::<&str>(query)
+            .map_err(|error| Error::Parse(error.to_string()))?;
+        let (items, scalars) = input::InputGenerator::new(&schema).generate(&document)?;
+        Ok(render::render(
+            &items,
+            Direction::Input,
+            &scalars,
+            &self.scalars_path,
+        ))
+    }
+
+    /// Generates the types of the function result.
+    pub fn output_types(&self, schema: &str) -> Result<String, Error> {
+        let schema = Schema::parse(schema)?;
+        let (items, scalars) = output::generate(&schema)?;
+        Ok(render::render(
+            &items,
+            Direction::Output,
+            &scalars,
+            &self.scalars_path,
+        ))
+    }
+
+    /// Generates the enums `names` as they'd be generated for the input.
+    pub fn enum_types(&self, schema: &str, names: &[&str]) -> Result<String, Error> {
+        let schema = Schema::parse(schema)?;
+        let items = names
+            .iter()
+            .map(|name| schema.enum_item(name))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(render::render(
+            &items,
+            Direction::Input,
+            &Default::default(),
+            &self.scalars_path,
+        ))
+    }
+
+    /// Generates the `Config` of a checkout script from its `script.config.yml`.
+    pub fn config_types(&self, source: &str) -> Result<String, Error> {
+        script_config::generate(source, &self.scalars_path, &self.config_path)
+    }
+
+    /// Build script entry point writing the input types to `$OUT_DIR/<file_name>`.
+    pub fn write_input_types(
+        &self,
+        schema_path: impl AsRef<Path>,
+        query_path: impl AsRef<Path>,
+        file_name: &str,
+    ) {
+        let schema = read(schema_path.as_ref());
+        let query = read(query_path.as_ref());
+        let generated = self
+            .input_types(&schema, &query)
+            .unwrap_or_else(|error| panic!("{}: {}", query_path.as_ref().display(), error));
+        write(file_name, generated);
+    }
+
+    /// Build script entry point writing the output types to `$OUT_DIR/<file_name>`.
+    pub fn write_output_types(&self, schema_path: impl AsRef<Path>, file_name: &str) {
+        let schema = read(schema_path.as_ref());
+        let generated = self
+            .output_types(&schema)
+            .unwrap_or_else(|error| panic!("{}: {}", schema_path.as_ref().display(), error));
+        write(file_name, generated);
+    }
+
+    /// Build script entry point writing the enums `names` to `$OUT_DIR/<file_name>`.
+    pub fn write_enum_types(&self, schema_path: impl AsRef<Path>, names: &[&str], file_name: &str) {
+        let schema = read(schema_path.as_ref());
+        let generated = self
+            .enum_types(&schema, names)
+            .unwrap_or_else(|error| panic!("{}: {}", schema_path.as_ref().display(), error));
+        write(file_name, generated);
+    }
+
+    /// Build script entry point writing the `Config` to `$OUT_DIR/<file_name>`.
+    pub fn write_config_types(&self, config_path: impl AsRef<Path>, file_name: &str) {
+        let source = read(config_path.as_ref());
+        let generated = self
+            .config_types(&source)
+            .unwrap_or_else(|error| panic!("{}: {}", config_path.as_ref().display(), error));
+        write(file_name, generated);
+    }
+}
+
+fn read(path: &Path) -> String {
+    println!("cargo:rerun-if-changed={}", path.display());
+    std::fs::read_to_string(path).unwrap_or_else(|error| panic!("{}: {}", path.display(), error))
+}
+
+fn write(file_name: &str, generated: String) {
+    let out_dir = std::env::var_os("OUT_DIR").expect("OUT_DIR is only set for build scripts");
+    let path = PathBuf::from(out_dir).join(file_name);
+    std::fs::write(&path, generated)
+        .unwrap_or_else(|error| panic!("{}: {}", path.display(), error));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: &str = r#"
+        schema {
+          query: Input
+          mutation: MutationRoot
+        }
+
+        directive @oneOf on INPUT_OBJECT
+
+        scalar Decimal
+
+        type Input {
+          cart: Cart!
+          discountNode: DiscountNode!
+        }
+
+        type Cart {
+          lines: [CartLine!]!
+          deliveryMethod: DeliveryMethod
+        }
+
+        enum DeliveryMethod {
+          PICK_UP
+          SHIPPING
+        }
+
+        type CartLine {
+          id: ID!
+          quantity: Int!
+          merchandise: Merchandise!
+          cost: CartLineCost!
+        }
+
+        type CartLineCost {
+          amount: MoneyV2!
+          compareAtAmount: MoneyV2
+        }
+
+        type MoneyV2 {
+          amount: Decimal!
+          currencyCode: CurrencyCode!
+        }
+
+        enum CurrencyCode {
+          CAD
+          USD
+        }
+
+        union Merchandise = CustomProduct | ProductVariant
+
+        type CustomProduct {
+          isGiftCard: Boolean!
+        }
+
+        type ProductVariant {
+          id: ID!
+          product: Product!
+        }
+
+        type Product {
+          id: ID!
+          vendor: String
+          hasAnyTag(tags: [String!]! = []): Boolean!
+        }
+
+        type DiscountNode {
+          metafield(namespace: String!, key: String!): Metafield
+        }
+
+        type Metafield {
+          type: String!
+          value: String!
+        }
+
+        type MutationRoot {
+          handleResult(result: FunctionResult!): Void!
+        }
+
+        scalar Void
+
+        input FunctionResult {
+          discounts: [Discount!]!
+        }
+
+        input Discount {
+          message: String
+          targets: [Target!]!
+          value: Value!
+        }
+
+        input Target @oneOf {
+          productVariant: ProductVariantTarget
+        }
+
+        input ProductVariantTarget {
+          id: ID!
+          quantity: Int
+        }
+
+        input Value @oneOf {
+          fixedAmount: FixedAmount
+          percentage: Percentage
+        }
+
+        input FixedAmount {
+          amount: Decimal!
+          appliesToEachItem: Boolean
+        }
+
+        input Percentage {
+          value: Float!
+        }
+    "#;
+
+    fn input_types(query: &str) -> String {
+        Codegen::default().input_types(SCHEMA, query).unwrap()
+    }
+
+    #[test]
+    fn test_input_types_only_contain_selected_fields() {
+        let generated = input_types(r#"query Input { cart { lines { id quantity } } }"#);
+
+        assert!(generated.contains("pub struct Input {\n    pub cart: Cart,\n}"));
+        assert!(
+            generated.contains("pub struct CartLine {\n    pub id: ID,\n    pub quantity: Int,\n}")
+        );
+        assert!(generated.contains("use shopify_function_core::scalars::{ID, Int};"));
+        assert!(!generated.contains("DiscountNode"));
+    }
+
+    #[test]
+    fn test_union_fragments_become_optional_fields() {
+        let generated = input_types(
+            r#"query Input {
+              cart { lines { merchandise { __typename ... on ProductVariant { id } } } }
+            }"#,
+        );
+
+        assert!(generated.contains(
+            "pub struct Merchandise {\n    #[serde(rename = \"__typename\")]\n    pub typename: String,\n    pub id: Option<ID>,\n}"
+        ));
+    }
+
+    #[test]
+    fn test_aliases_and_arguments() {
+        let generated = input_types(
+            r#"query Input {
+              cart { lines { merchandise { ... on ProductVariant { product { isVip: hasAnyTag(tags: ["vip"]) vendor } } } } }
+            }"#,
+        );
+
+        assert!(generated.contains(
+            "pub struct Product {\n    pub is_vip: Boolean,\n    pub vendor: Option<String>,\n}"
+        ));
+    }
+
+    #[test]
+    fn test_same_type_with_different_selections() {
+        let generated = input_types(
+            r#"query Input {
+              cart { lines { cost { amount { amount } compareAtAmount { amount currencyCode } } } }
+            }"#,
+        );
+
+        assert!(generated.contains("pub struct MoneyV2 {\n    pub amount: Decimal,\n}"));
+        assert!(generated.contains("pub struct CartLineCostCompareAtAmount {"));
+        assert!(
+            generated.contains("    pub compare_at_amount: Option<CartLineCostCompareAtAmount>,\n")
+        );
+    }
+
+    #[test]
+    fn test_enums() {
+        let generated = input_types(r#"query Input { cart { deliveryMethod } }"#);
+
+        assert!(generated.contains("    pub delivery_method: Option<DeliveryMethod>,\n"));
+        assert!(generated.contains(
+            "#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]\n#[serde(rename_all = \"SCREAMING_SNAKE_CASE\")]\npub enum DeliveryMethod {\n    PickUp,\n    Shipping,\n"
+        ));
+        assert!(generated.contains("#[serde(other)]\n    Other,"));
+
+        let generated = Codegen::default()
+            .enum_types(SCHEMA, &["DeliveryMethod"])
+            .unwrap();
+        assert!(generated.contains("pub enum DeliveryMethod {"));
+    }
+
+    #[test]
+    fn test_provided_types_are_imported() {
+        let generated = input_types(
+            r#"query Input { cart { lines { cost { amount { amount currencyCode } } } } }"#,
+        );
+
+        assert!(generated.contains("use shopify_function_core::scalars::{CurrencyCode, Decimal};"));
+        assert!(generated.contains("    pub currency_code: CurrencyCode,\n"));
+        assert!(!generated.contains("pub enum CurrencyCode"));
+    }
+
+    #[test]
+    fn test_identical_selections_share_a_struct() {
+        let generated = input_types(
+            r#"query Input {
+              cart { lines { cost { amount { amount } compareAtAmount { amount } } } }
+            }"#,
+        );
+
+        assert!(generated.contains("    pub compare_at_amount: Option<MoneyV2>,\n"));
+        assert!(!generated.contains("CartLineCostCompareAtAmount"));
+    }
+
+    #[test]
+    fn test_keyword_fields_and_fragments() {
+        let generated = input_types(
+            r#"query Input { discountNode { metafield(namespace: "a", key: "b") { ...Value } } }
+            fragment Value on Metafield { type value }"#,
+        );
+
+        assert!(generated.contains("    pub r#type: String,\n    pub value: String,\n"));
+    }
+
+    #[test]
+    fn test_input_errors() {
+        let codegen = Codegen::default();
+
+        assert!(matches!(
+            codegen.input_types(SCHEMA, "query Input { cart { total } }"),
+            Err(Error::UnknownField { .. })
+        ));
+        assert!(matches!(
+            codegen.input_types(SCHEMA, "query Input { cart }"),
+            Err(Error::MissingSelection { .. })
+        ));
+        assert!(matches!(
+            codegen.input_types(SCHEMA, "query Input { cart {"),
+            Err(Error::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_output_types() {
+        let generated = Codegen::default().output_types(SCHEMA).unwrap();
+
+        assert!(
+            generated.contains("pub struct FunctionResult {\n    pub discounts: Vec<Discount>,\n}")
+        );
+        assert!(generated.contains(
+            "#[serde_with::skip_serializing_none]\n#[derive(Clone, Debug, PartialEq, serde::Serialize)]\npub struct Discount {"
+        ));
+        assert!(generated.contains(
+            "    ProductVariant {\n        id: ID,\n        quantity: Option<Int>,\n    },"
+        ));
+        assert!(generated.contains("    FixedAmount(FixedAmount),\n    Percentage(Percentage),"));
+        assert!(generated.contains("pub struct FixedAmount {\n    pub amount: Decimal,\n    pub applies_to_each_item: Option<Boolean>,\n}"));
+        assert!(!generated.contains("pub struct ProductVariantTarget"));
+    }
+
+    const SCRIPT_CONFIG: &str = r#"---
+version: '2'
+title: Filter payment method on threshold
+description: Hide payment methods based on the order amount
+configuration:
+  type: object
+  fields:
+    paymentMethodName:
+      name: Payment methods to hide
+      type: single_line_text_field
+    threshold:
+      name: Order amount
+      type: number_decimal
+      helpText: Checkout totals greater than this threshold will hide the
+        payment method.
+    countryCodes:
+      type: list.single_line_text_field
+      required: false
+    comparison:
+      type: single_line_text_field
+      default: '>'
+      choices:
+        greater_than: '>'
+        less_than: '<'
+    sortDirection:
+      type: single_line_text_field
+      choices: [ASCENDING, DESCENDING]
+"#;
+
+    #[test]
+    fn test_config_types() {
+        let generated = Codegen::default().config_types(SCRIPT_CONFIG).unwrap();
+
+        assert!(generated.contains("use shopify_function_core::scalars::Decimal;"));
+        assert!(generated.contains(
+            "/// Hide payment methods based on the order amount\n#[derive(Clone, Debug, PartialEq)]\npub struct Config {"
+        ));
+        assert!(generated.contains(
+            "    /// Order amount\n    ///\n    /// Checkout totals greater than this threshold will hide the payment method.\n    pub threshold: Decimal,\n"
+        ));
+        assert!(generated.contains("    pub country_codes: Option<Vec<String>>,\n"));
+        assert!(generated.contains("    pub comparison: Comparison,\n"));
+        assert!(generated.contains("pub enum Comparison {\n    GreaterThan,\n    LessThan,\n}"));
+        assert!(generated.contains("pub enum SortDirection {\n    Ascending,\n    Descending,\n}"));
+        assert!(generated.contains("const EXPECTED: &'static str = \"one of `>`, `<`\";"));
+        assert!(generated.contains("            \">\" => Some(Comparison::GreaterThan),\n"));
+        assert!(generated.contains(
+            "                payment_method_name: values.required(\"paymentMethodName\")?,\n"
+        ));
+        assert!(generated
+            .contains("                country_codes: values.optional(\"countryCodes\")?,\n"));
+        assert!(generated.contains(
+            "                comparison: values.with_default(\"comparison\", \">\")?,\n"
+        ));
+    }
+
+    #[test]
+    fn test_config_quoted_help_text() {
+        let generated = Codegen::default()
+            .config_types(
+                r#"
+configuration:
+  type: object
+  fields:
+    rules:
+      type: multi_line_text_field
+      required: false
+      helpText: 'A JSON list, e.g. [{"hide": {"name":
+        "Cash*"}}]. It''s applied in order.'
+    enabled:
+      type: boolean
+      default: true
+"#,
+            )
+            .unwrap();
+
+        assert!(generated.contains(
+            "    /// A JSON list, e.g. [{\"hide\": {\"name\": \"Cash*\"}}]. It's applied in order.\n    pub rules: Option<String>,\n"
+        ));
+        assert!(generated
+            .contains("                enabled: values.with_default(\"enabled\", \"true\")?,\n"));
+    }
+
+    #[test]
+    fn test_config_errors() {
+        let codegen = Codegen::default();
+
+        assert!(codegen
+            .config_types("configuration:\n  type: object\n  fields: {}\n")
+            .unwrap()
+            .contains("pub struct Config {\n}"));
+        assert!(matches!(
+            codegen.config_types("configuration:\n  type: list\n  schema: []\n"),
+            Err(Error::Unsupported(_))
+        ));
+        assert!(matches!(
+            codegen.config_types(
+                "configuration:\n  type: object\n  fields:\n    a:\n      type: json\n"
+            ),
+            Err(Error::Unsupported(_))
+        ));
+        assert!(matches!(
+            codegen.config_types(
+                "configuration:\n  type: object\n  fields:\n    a:\n      type: single_line_text_field\n      choices: ['>']\n"
+            ),
+            Err(Error::Unsupported(_))
+        ));
+        assert!(matches!(
+            codegen.config_types("configuration:\n  type: 'object\n  fields: {}\n"),
+            Err(Error::ScriptConfig(_))
+        ));
+        assert!(matches!(
+            codegen
+                .config_types("configuration:\n  type: object\n  fields:\n    a:\n      name: A\n"),
+            Err(Error::ScriptConfig(_))
+        ));
+    }
+}
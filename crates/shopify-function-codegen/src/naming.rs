@@ -0,0 +1,125 @@
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop",
+    "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "static",
+    "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual",
+    "where", "while", "yield",
+];
+
+/// The Rust field name for a GraphQL field or response key.
+pub fn field_name(key: &str) -> String {
+    let name = snake_case(key.trim_start_matches('_'));
+    match name.as_str() {
+        "self" | "super" | "crate" => format!("{}_", name),
+        _ if KEYWORDS.contains(&name.as_str()) => format!("r#{}", name),
+        _ => name,
+    }
+}
+
+/// The Rust type or variant name for a GraphQL name.
+pub fn type_name(name: &str) -> String {
+    name.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            let first = chars.next().unwrap().to_ascii_uppercase();
+            if part.chars().any(|c| c.is_ascii_lowercase()) {
+                std::iter::once(first).chain(chars).collect::<String>()
+            } else {
+                std::iter::once(first)
+                    .chain(chars.map(|c| c.to_ascii_lowercase()))
+                    .collect::<String>()
+            }
+        })
+        .collect()
+}
+
+pub fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    let mut previous_lowercase = false;
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            if previous_lowercase {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+            previous_lowercase = false;
+        } else {
+            snake.push(c);
+            previous_lowercase = c.is_ascii_lowercase() || c.is_ascii_digit();
+        }
+    }
+    snake
+}
+
+/// What `#[serde(rename_all = "camelCase")]` turns a field name into.
+pub fn serde_camel_case_field(field: &str) -> String {
+    let field = field.trim_start_matches("r#");
+    let mut camel = String::new();
+    let mut capitalize = false;
+    for c in field.chars() {
+        if c == '_' {
+            capitalize = !camel.is_empty();
+        } else if capitalize {
+            camel.push(c.to_ascii_uppercase());
+            capitalize = false;
+        } else {
+            camel.push(c);
+        }
+    }
+    camel
+}
+
+/// What `#[serde(rename_all = "camelCase")]` turns a variant name into.
+pub fn serde_camel_case_variant(variant: &str) -> String {
+    let mut chars = variant.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+/// What `#[serde(rename_all = "SCREAMING_SNAKE_CASE")]` turns a variant name into.
+pub fn serde_screaming_snake_case_variant(variant: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in variant.char_indices() {
+        if i > 0 && c.is_uppercase() {
+            snake.push('_');
+        }
+        snake.push(c.to_ascii_uppercase());
+    }
+    snake
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_name() {
+        assert_eq!(field_name("excludedVariantIds"), "excluded_variant_ids");
+        assert_eq!(field_name("isoCode"), "iso_code");
+        assert_eq!(field_name("__typename"), "typename");
+        assert_eq!(field_name("type"), "r#type");
+        assert_eq!(field_name("ID"), "id");
+    }
+
+    #[test]
+    fn test_type_name() {
+        assert_eq!(type_name("PICK_UP"), "PickUp");
+        assert_eq!(type_name("PT_BR"), "PtBr");
+        assert_eq!(type_name("productVariant"), "ProductVariant");
+        assert_eq!(type_name("MoneyV2"), "MoneyV2");
+    }
+
+    #[test]
+    fn test_serde_round_trips() {
+        assert_eq!(
+            serde_camel_case_field("excluded_variant_ids"),
+            "excludedVariantIds"
+        );
+        assert_eq!(serde_camel_case_field("r#type"), "type");
+        assert_eq!(serde_camel_case_variant("ProductVariant"), "productVariant");
+        assert_eq!(serde_screaming_snake_case_variant("PickUp"), "PICK_UP");
+    }
+}
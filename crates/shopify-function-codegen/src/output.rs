@@ -0,0 +1,104 @@
+use std::collections::{BTreeSet, VecDeque};
+
+use graphql_parser::schema::{InputValue, TypeDefinition};
+
+use crate::naming;
+use crate::render::{Field, Item, Variant};
+use crate::schema::{self, Schema};
+use crate::Error;
+
+/// Generates the types reachable from the input type of the result mutation.
+pub fn generate<'q>(schema: &Schema<'q>) -> Result<(Vec<Item>, BTreeSet<&'q str>), Error> {
+    let mut items = Vec::new();
+    let mut scalars = BTreeSet::new();
+    let mut seen = vec![schema.result_type()?];
+    let mut queue: VecDeque<&str> = seen.iter().copied().collect();
+
+    while let Some(name) = queue.pop_front() {
+        let mut referenced = Vec::new();
+        let item = match schema.get(name)? {
+            TypeDefinition::Enum(_) => schema.enum_item(name)?,
+            TypeDefinition::InputObject(definition) => {
+                let (input_fields, one_of) = schema.input_fields(name)?;
+                if one_of {
+                    let mut variants = Vec::new();
+                    for input_field in input_fields {
+                        let member = schema::named_type(&input_field.value_type);
+                        let doc = input_field.description.clone();
+                        let key = input_field.name.to_string();
+                        if naming::type_name(&key) == member {
+                            referenced.push(member);
+                            variants.push(Variant::Newtype {
+                                key,
+                                ty: naming::type_name(member),
+                                doc,
+                            });
+                        } else {
+                            let (member_fields, _) = schema.input_fields(member)?;
+                            let fields =
+                                fields(schema, member_fields, &mut referenced, &mut scalars);
+                            variants.push(Variant::Struct { key, fields, doc });
+                        }
+                    }
+                    Item::OneOf {
+                        name: naming::type_name(name),
+                        doc: definition.description.clone(),
+                        variants,
+                    }
+                } else {
+                    Item::Struct {
+                        name: naming::type_name(name),
+                        doc: definition.description.clone(),
+                        fields: fields(schema, input_fields, &mut referenced, &mut scalars),
+                    }
+                }
+            }
+            _ => {
+                return Err(Error::Unsupported(format!(
+                    "`{}` is not an input or enum type",
+                    name
+                )))
+            }
+        };
+        items.push(item);
+
+        for name in referenced {
+            if !seen.contains(&name) {
+                seen.push(name);
+                queue.push_back(name);
+            }
+        }
+    }
+
+    Ok((items, scalars))
+}
+
+fn fields<'q>(
+    schema: &Schema<'q>,
+    input_fields: &[InputValue<'q, &'q str>],
+    referenced: &mut Vec<&'q str>,
+    scalars: &mut BTreeSet<&'q str>,
+) -> Vec<Field> {
+    input_fields
+        .iter()
+        .map(|input_field| {
+            let field_type = schema::named_type(&input_field.value_type);
+            let inner = if schema.is_scalar(field_type) {
+                let (ty, imported) = schema::scalar_type(field_type);
+                if imported {
+                    scalars.insert(field_type);
+                }
+                ty.to_string()
+            } else {
+                referenced.push(field_type);
+                naming::type_name(field_type)
+            };
+            Field {
+                name: naming::field_name(input_field.name),
+                key: input_field.name.to_string(),
+                ty: schema::rust_type(&input_field.value_type, &inner),
+                doc: input_field.description.clone(),
+            }
+        })
+        .collect()
+}
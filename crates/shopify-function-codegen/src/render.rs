@@ -0,0 +1,298 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use crate::naming;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Direction {
+    /// Types read from the function input, derived with `Deserialize`.
+    Input,
+    /// Types written as the function result, derived with `Serialize`.
+    Output,
+}
+
+impl Direction {
+    fn derive(self) -> &'static str {
+        match self {
+            Direction::Input => "serde::Deserialize",
+            Direction::Output => "serde::Serialize",
+        }
+    }
+
+    fn serde(self) -> &'static str {
+        match self {
+            Direction::Input => "deserialize",
+            Direction::Output => "serialize",
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Field {
+    /// The Rust field name.
+    pub name: String,
+    /// The JSON key of the field.
+    pub key: String,
+    pub ty: String,
+    pub doc: Option<String>,
+}
+
+impl Field {
+    fn is_option(&self) -> bool {
+        self.ty.starts_with("Option<")
+    }
+}
+
+pub enum Variant {
+    /// A one-of member wrapping a type of the same name, e.g. `Percentage(Percentage)`.
+    Newtype {
+        key: String,
+        ty: String,
+        doc: Option<String>,
+    },
+    /// A one-of member with the fields of its type inlined.
+    Struct {
+        key: String,
+        fields: Vec<Field>,
+        doc: Option<String>,
+    },
+}
+
+pub enum Item {
+    Struct {
+        name: String,
+        doc: Option<String>,
+        fields: Vec<Field>,
+    },
+    Enum {
+        name: String,
+        doc: Option<String>,
+        values: Vec<(String, Option<String>)>,
+    },
+    OneOf {
+        name: String,
+        doc: Option<String>,
+        variants: Vec<Variant>,
+    },
+}
+
+pub fn render(
+    items: &[Item],
+    direction: Direction,
+    scalars: &BTreeSet<&str>,
+    path: &str,
+) -> String {
+    let mut out = String::from("// @generated by shopify-function-codegen. Do not edit.\n\n");
+    if !scalars.is_empty() {
+        // Output types are the public API of their module, so the scalars
+        // they're made of are re-exported alongside them.
+        let visibility = match direction {
+            Direction::Input => "",
+            Direction::Output => "pub ",
+        };
+        let scalars: Vec<&str> = scalars.iter().copied().collect();
+        if scalars.len() == 1 {
+            writeln!(out, "{}use {}::{};\n", visibility, path, scalars[0]).unwrap();
+        } else {
+            writeln!(
+                out,
+                "{}use {}::{{{}}};\n",
+                visibility,
+                path,
+                scalars.join(", ")
+            )
+            .unwrap();
+        }
+    }
+
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        match item {
+            Item::Struct { name, doc, fields } => {
+                render_struct(&mut out, name, doc.as_deref(), fields, direction)
+            }
+            Item::Enum { name, doc, values } => {
+                render_enum(&mut out, name, doc.as_deref(), values, direction)
+            }
+            Item::OneOf {
+                name,
+                doc,
+                variants,
+            } => render_one_of(&mut out, name, doc.as_deref(), variants, direction),
+        }
+    }
+    out
+}
+
+pub fn render_doc(out: &mut String, doc: Option<&str>, indent: &str) {
+    if let Some(doc) = doc {
+        for line in doc.trim().lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                writeln!(out, "{}///", indent).unwrap();
+            } else {
+                writeln!(out, "{}/// {}", indent, line).unwrap();
+            }
+        }
+    }
+}
+
+/// Whether `rename_all = "camelCase"` is needed, and the fields that still need a rename.
+fn renames(fields: &[Field]) -> (bool, Vec<Option<&str>>) {
+    let camel_case = fields.iter().any(|field| {
+        field.name != field.key && naming::serde_camel_case_field(&field.name) == field.key
+    });
+    let renames = fields
+        .iter()
+        .map(|field| {
+            let serialized = if camel_case {
+                naming::serde_camel_case_field(&field.name)
+            } else {
+                field.name.trim_start_matches("r#").to_string()
+            };
+            (serialized != field.key).then_some(field.key.as_str())
+        })
+        .collect();
+    (camel_case, renames)
+}
+
+fn render_fields(
+    out: &mut String,
+    fields: &[Field],
+    renames: &[Option<&str>],
+    indent: &str,
+    public: bool,
+) {
+    for (field, rename) in fields.iter().zip(renames) {
+        render_doc(out, field.doc.as_deref(), indent);
+        if let Some(key) = rename {
+            writeln!(out, "{}#[serde(rename = \"{}\")]", indent, key).unwrap();
+        }
+        let visibility = if public { "pub " } else { "" };
+        writeln!(out, "{}{}{}: {},", indent, visibility, field.name, field.ty).unwrap();
+    }
+}
+
+fn render_struct(
+    out: &mut String,
+    name: &str,
+    doc: Option<&str>,
+    fields: &[Field],
+    direction: Direction,
+) {
+    render_doc(out, doc, "");
+    if direction == Direction::Output && fields.iter().any(Field::is_option) {
+        out.push_str("#[serde_with::skip_serializing_none]\n");
+    }
+    writeln!(
+        out,
+        "#[derive(Clone, Debug, PartialEq, {})]",
+        direction.derive()
+    )
+    .unwrap();
+    let (camel_case, renames) = renames(fields);
+    if camel_case {
+        writeln!(
+            out,
+            "#[serde(rename_all({} = \"camelCase\"))]",
+            direction.serde()
+        )
+        .unwrap();
+    }
+    writeln!(out, "pub struct {} {{", name).unwrap();
+    render_fields(out, fields, &renames, "    ", true);
+    out.push_str("}\n");
+}
+
+fn render_enum(
+    out: &mut String,
+    name: &str,
+    doc: Option<&str>,
+    values: &[(String, Option<String>)],
+    direction: Direction,
+) {
+    // Enums go both ways so configurations can refer to schema values, e.g. a
+    // `CountryCode`.
+    render_doc(out, doc, "");
+    out.push_str(
+        "#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]\n",
+    );
+    out.push_str("#[serde(rename_all = \"SCREAMING_SNAKE_CASE\")]\n");
+    writeln!(out, "pub enum {} {{", name).unwrap();
+    let mut variants = Vec::new();
+    for (value, doc) in values {
+        let variant = naming::type_name(value);
+        render_doc(out, doc.as_deref(), "    ");
+        if naming::serde_screaming_snake_case_variant(&variant) != *value {
+            writeln!(out, "    #[serde(rename = \"{}\")]", value).unwrap();
+        }
+        writeln!(out, "    {},", variant).unwrap();
+        variants.push(variant);
+    }
+    if direction == Direction::Input && !variants.iter().any(|variant| variant == "Other") {
+        out.push_str("    /// A value added to the schema after these types were generated.\n");
+        out.push_str("    #[serde(other)]\n");
+        out.push_str("    Other,\n");
+    }
+    out.push_str("}\n");
+}
+
+fn render_one_of(
+    out: &mut String,
+    name: &str,
+    doc: Option<&str>,
+    variants: &[Variant],
+    direction: Direction,
+) {
+    render_doc(out, doc, "");
+    let has_option = variants.iter().any(|variant| match variant {
+        Variant::Struct { fields, .. } => fields.iter().any(Field::is_option),
+        Variant::Newtype { .. } => false,
+    });
+    if direction == Direction::Output && has_option {
+        out.push_str("#[serde_with::skip_serializing_none]\n");
+    }
+    writeln!(
+        out,
+        "#[derive(Clone, Debug, PartialEq, {})]",
+        direction.derive()
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "#[serde(rename_all({} = \"camelCase\"))]",
+        direction.serde()
+    )
+    .unwrap();
+    writeln!(out, "pub enum {} {{", name).unwrap();
+    for variant in variants {
+        let (key, doc) = match variant {
+            Variant::Newtype { key, doc, .. } | Variant::Struct { key, doc, .. } => (key, doc),
+        };
+        let variant_name = naming::type_name(key);
+        render_doc(out, doc.as_deref(), "    ");
+        if naming::serde_camel_case_variant(&variant_name) != *key {
+            writeln!(out, "    #[serde(rename = \"{}\")]", key).unwrap();
+        }
+        match variant {
+            Variant::Newtype { ty, .. } => writeln!(out, "    {}({}),", variant_name, ty).unwrap(),
+            Variant::Struct { fields, .. } => {
+                let (camel_case, renames) = renames(fields);
+                if camel_case {
+                    writeln!(
+                        out,
+                        "    #[serde(rename_all({} = \"camelCase\"))]",
+                        direction.serde()
+                    )
+                    .unwrap();
+                }
+                writeln!(out, "    {} {{", variant_name).unwrap();
+                render_fields(out, fields, &renames, "        ", false);
+                out.push_str("    },\n");
+            }
+        }
+    }
+    out.push_str("}\n");
+}
@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+
+use graphql_parser::schema::{self, Definition, Field, InputValue, Type, TypeDefinition};
+
+use crate::naming;
+use crate::render::Item;
+use crate::Error;
+
+const BUILTIN_SCALARS: &[&str] = &["Boolean", "Float", "ID", "Int", "String"];
+
+/// Schema types the scalars module provides, which are imported like scalars
+/// instead of being generated.
+const PROVIDED_TYPES: &[&str] = &["CurrencyCode", "LanguageCode"];
+
+/// An index over the type definitions of a parsed `schema.graphql`.
+pub struct Schema<'a> {
+    types: HashMap<&'a str, TypeDefinition<'a, &'a str>>,
+    query_type: &'a str,
+    mutation_type: Option<&'a str>,
+}
+
+impl<'a> Schema<'a> {
+    pub fn parse(source: &'a str) -> Result<Self, Error> {
+        let document = schema::parse_schema::<&str>(source)
+            .map_err(|error| Error::Parse(error.to_string()))?;
+
+        let mut types = HashMap::new();
+        let mut query_type = "Query";
+        let mut mutation_type = None;
+        for definition in document.definitions {
+            match definition {
+                Definition::SchemaDefinition(definition) => {
+                    query_type = definition.query.unwrap_or(query_type);
+                    mutation_type = definition.mutation;
+                }
+                Definition::TypeDefinition(definition) => {
+                    types.insert(type_definition_name(&definition), definition);
+                }
+                Definition::TypeExtension(_) | Definition::DirectiveDefinition(_) => {}
+            }
+        }
+
+        Ok(Schema {
+            types,
+            query_type,
+            mutation_type,
+        })
+    }
+
+    pub fn query_type(&self) -> &'a str {
+        self.query_type
+    }
+
+    /// The input type passed to the mutation that handles the function result.
+    pub fn result_type(&self) -> Result<&'a str, Error> {
+        let mutation_type = match self.mutation_type {
+            Some(name) => name,
+            None => return Ok("FunctionResult"),
+        };
+        self.fields(mutation_type)?
+            .iter()
+            .flat_map(|field| field.arguments.iter())
+            .map(|argument| named_type(&argument.value_type))
+            .next()
+            .ok_or_else(|| Error::Unsupported(format!("`{}` has no arguments", mutation_type)))
+    }
+
+    pub fn get(&self, name: &str) -> Result<&TypeDefinition<'a, &'a str>, Error> {
+        self.types
+            .get(name)
+            .ok_or_else(|| Error::UnknownType(name.to_string()))
+    }
+
+    pub fn enum_item(&self, name: &str) -> Result<Item, Error> {
+        match self.get(name)? {
+            TypeDefinition::Enum(definition) => Ok(Item::Enum {
+                name: naming::type_name(name),
+                doc: definition.description.clone(),
+                values: definition
+                    .values
+                    .iter()
+                    .map(|value| (value.name.to_string(), value.description.clone()))
+                    .collect(),
+            }),
+            _ => Err(Error::Unsupported(format!("`{}` is not an enum", name))),
+        }
+    }
+
+    /// Whether `name` is a scalar, or a type provided by the scalars module.
+    pub fn is_scalar(&self, name: &str) -> bool {
+        BUILTIN_SCALARS.contains(&name)
+            || PROVIDED_TYPES.contains(&name)
+            || matches!(self.types.get(name), Some(TypeDefinition::Scalar(_)))
+    }
+
+    pub fn description(&self, name: &str) -> Option<&str> {
+        match self.types.get(name)? {
+            TypeDefinition::Scalar(t) => t.description.as_deref(),
+            TypeDefinition::Object(t) => t.description.as_deref(),
+            TypeDefinition::Interface(t) => t.description.as_deref(),
+            TypeDefinition::Union(t) => t.description.as_deref(),
+            TypeDefinition::Enum(t) => t.description.as_deref(),
+            TypeDefinition::InputObject(t) => t.description.as_deref(),
+        }
+    }
+
+    /// The fields of an object or interface type.
+    pub fn fields(&self, name: &str) -> Result<&[Field<'a, &'a str>], Error> {
+        match self.get(name)? {
+            TypeDefinition::Object(object) => Ok(&object.fields),
+            TypeDefinition::Interface(interface) => Ok(&interface.fields),
+            TypeDefinition::Union(_) => Ok(&[]),
+            _ => Err(Error::Unsupported(format!(
+                "`{}` is not an object, interface or union",
+                name
+            ))),
+        }
+    }
+
+    pub fn field(&self, type_name: &str, field: &str) -> Result<&Field<'a, &'a str>, Error> {
+        self.fields(type_name)?
+            .iter()
+            .find(|candidate| candidate.name == field)
+            .ok_or_else(|| Error::UnknownField {
+                type_name: type_name.to_string(),
+                field: field.to_string(),
+            })
+    }
+
+    /// The fields of an input object type, and whether it is marked `@oneOf`.
+    pub fn input_fields(&self, name: &str) -> Result<(&[InputValue<'a, &'a str>], bool), Error> {
+        match self.get(name)? {
+            TypeDefinition::InputObject(input) => Ok((
+                &input.fields,
+                input.directives.iter().any(|d| d.name == "oneOf"),
+            )),
+            _ => Err(Error::Unsupported(format!(
+                "`{}` is not an input type",
+                name
+            ))),
+        }
+    }
+}
+
+fn type_definition_name<'a>(definition: &TypeDefinition<'a, &'a str>) -> &'a str {
+    match definition {
+        TypeDefinition::Scalar(t) => t.name,
+        TypeDefinition::Object(t) => t.name,
+        TypeDefinition::Interface(t) => t.name,
+        TypeDefinition::Union(t) => t.name,
+        TypeDefinition::Enum(t) => t.name,
+        TypeDefinition::InputObject(t) => t.name,
+    }
+}
+
+/// The innermost named type, e.g. `CartLine` for `[CartLine!]!`.
+pub fn named_type<'a>(ty: &Type<'a, &'a str>) -> &'a str {
+    match ty {
+        Type::NamedType(name) => name,
+        Type::ListType(inner) | Type::NonNullType(inner) => named_type(inner),
+    }
+}
+
+/// Wraps `inner` in `Option` and `Vec` following the nullability and lists of `ty`.
+pub fn rust_type<'a>(ty: &Type<'a, &'a str>, inner: &str) -> String {
+    match ty {
+        Type::NonNullType(ty) => match ty.as_ref() {
+            Type::ListType(item) => format!("Vec<{}>", rust_type(item, inner)),
+            _ => inner.to_string(),
+        },
+        Type::ListType(item) => format!("Option<Vec<{}>>", rust_type(item, inner)),
+        Type::NamedType(_) => format!("Option<{}>", inner),
+    }
+}
+
+/// The Rust type for a scalar, and whether it comes from the scalars module.
+pub fn scalar_type(name: &str) -> (&str, bool) {
+    match name {
+        "Boolean" | "Decimal" | "Float" | "ID" | "Int" => (name, true),
+        _ if PROVIDED_TYPES.contains(&name) => (name, true),
+        "Void" => ("()", false),
+        _ => ("String", false),
+    }
+}
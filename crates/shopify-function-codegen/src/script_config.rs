@@ -0,0 +1,309 @@
+use std::fmt::Write;
+
+use indexmap::IndexMap;
+use serde::Deserialize;
+
+use crate::naming;
+use crate::render::render_doc;
+use crate::Error;
+
+/// The parts of a `script.config.yml` the `Config` is generated from.
+#[derive(Deserialize)]
+struct ScriptConfig {
+    description: Option<String>,
+    configuration: Configuration,
+}
+
+#[derive(Deserialize)]
+struct Configuration {
+    #[serde(rename = "type")]
+    ty: String,
+    #[serde(default)]
+    fields: Option<IndexMap<String, FieldDefinition>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FieldDefinition {
+    #[serde(rename = "type")]
+    ty: String,
+    name: Option<String>,
+    help_text: Option<String>,
+    default: Option<serde_yaml::Value>,
+    required: Option<bool>,
+    choices: Option<Choices>,
+}
+
+/// Either a list of values that are valid variant names or a mapping of
+/// variant names to values.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Choices {
+    List(Vec<String>),
+    Mapping(IndexMap<String, String>),
+}
+
+struct Field {
+    key: String,
+    name: String,
+    doc: Option<String>,
+    /// The Rust type, without the `Option` of optional fields.
+    ty: String,
+    presence: Presence,
+}
+
+enum Presence {
+    Required,
+    Optional,
+    Default(String),
+}
+
+struct Enum {
+    name: String,
+    /// The variant names and the configuration values they're read from.
+    variants: Vec<(String, String)>,
+}
+
+/// Generates the `Config` struct for the fields of a `script.config.yml`,
+/// deserialized from the configuration values by coercing strings to the
+/// types of the fields.
+pub fn generate(source: &str, scalars_path: &str, config_path: &str) -> Result<String, Error> {
+    let document: ScriptConfig =
+        serde_yaml::from_str(source).map_err(|error| Error::ScriptConfig(error.to_string()))?;
+    if document.configuration.ty != "object" {
+        return Err(Error::Unsupported(format!(
+            "configurations of type `{}`, only `object` is supported",
+            document.configuration.ty
+        )));
+    }
+
+    let mut fields = Vec::new();
+    let mut enums = Vec::new();
+    let mut scalars = Vec::new();
+    let definitions = document.configuration.fields.unwrap_or_default();
+    for (key, definition) in &definitions {
+        let (list, field_type) = match definition.ty.strip_prefix("list.") {
+            Some(item_type) => (true, item_type),
+            None => (false, definition.ty.as_str()),
+        };
+        let mut ty = match &definition.choices {
+            Some(choices) => {
+                let item = enum_item(key, choices)?;
+                let name = item.name.clone();
+                enums.push(item);
+                name
+            }
+            None => scalar_type(key, field_type, &mut scalars)?.to_string(),
+        };
+        if list {
+            ty = format!("Vec<{}>", ty);
+        }
+
+        let presence = match (&definition.default, definition.required) {
+            (Some(default), _) => Presence::Default(default_value(key, default)?),
+            (None, Some(false)) => Presence::Optional,
+            (None, _) => Presence::Required,
+        };
+        let doc = [&definition.name, &definition.help_text]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        fields.push(Field {
+            key: key.clone(),
+            name: naming::field_name(key),
+            doc: (!doc.is_empty()).then_some(doc),
+            ty,
+            presence,
+        });
+    }
+
+    Ok(render(
+        document.description.as_deref(),
+        &fields,
+        &enums,
+        &scalars,
+        scalars_path,
+        config_path,
+    ))
+}
+
+/// A field's `default`, as the string value the configuration would hold.
+fn default_value(key: &str, default: &serde_yaml::Value) -> Result<String, Error> {
+    match default {
+        serde_yaml::Value::String(value) => Ok(value.clone()),
+        serde_yaml::Value::Number(value) => Ok(value.to_string()),
+        serde_yaml::Value::Bool(value) => Ok(value.to_string()),
+        _ => Err(Error::ScriptConfig(format!(
+            "the default of field `{}` must be a string, a number or a boolean",
+            key
+        ))),
+    }
+}
+
+fn scalar_type<'a>(
+    key: &str,
+    field_type: &'a str,
+    scalars: &mut Vec<&'a str>,
+) -> Result<&'a str, Error> {
+    match field_type {
+        "single_line_text_field"
+        | "multi_line_text_field"
+        | "url"
+        | "color"
+        | "date"
+        | "date_time" => Ok("String"),
+        "boolean" => Ok("bool"),
+        "number_integer" => Ok("i64"),
+        "number_decimal" => {
+            if !scalars.contains(&"Decimal") {
+                scalars.push("Decimal");
+            }
+            Ok("Decimal")
+        }
+        _ => Err(Error::Unsupported(format!(
+            "field `{}` of type `{}`",
+            key, field_type
+        ))),
+    }
+}
+
+/// The enum for a field's `choices`.
+fn enum_item(key: &str, choices: &Choices) -> Result<Enum, Error> {
+    let variants = match choices {
+        Choices::List(values) => values
+            .iter()
+            .map(|value| {
+                let variant = naming::type_name(value);
+                let valid = variant.chars().next().is_some_and(char::is_alphabetic)
+                    && variant.chars().all(|c| c.is_ascii_alphanumeric());
+                if valid {
+                    Ok((variant, value.clone()))
+                } else {
+                    Err(Error::Unsupported(format!(
+                        "choice `{}` of field `{}` isn't a valid name, use a mapping of names to values",
+                        value, key
+                    )))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        Choices::Mapping(entries) => entries
+            .iter()
+            .map(|(name, value)| (naming::type_name(name), value.clone()))
+            .collect(),
+    };
+    if variants.is_empty() {
+        return Err(Error::ScriptConfig(format!(
+            "field `{}` has no choices",
+            key
+        )));
+    }
+    Ok(Enum {
+        name: naming::type_name(key),
+        variants,
+    })
+}
+
+fn render(
+    description: Option<&str>,
+    fields: &[Field],
+    enums: &[Enum],
+    scalars: &[&str],
+    scalars_path: &str,
+    config_path: &str,
+) -> String {
+    let mut out = String::from("// @generated by shopify-function-codegen. Do not edit.\n\n");
+    if !scalars.is_empty() {
+        writeln!(out, "use {}::{};\n", scalars_path, scalars.join(", ")).unwrap();
+    }
+
+    render_doc(&mut out, description, "");
+    out.push_str("#[derive(Clone, Debug, PartialEq)]\npub struct Config {\n");
+    for field in fields {
+        render_doc(&mut out, field.doc.as_deref(), "    ");
+        let ty = match field.presence {
+            Presence::Optional => format!("Option<{}>", field.ty),
+            Presence::Required | Presence::Default(_) => field.ty.clone(),
+        };
+        writeln!(out, "    pub {}: {},", field.name, ty).unwrap();
+    }
+    out.push_str("}\n");
+
+    for item in enums {
+        writeln!(
+            out,
+            "\n#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]\npub enum {} {{",
+            item.name
+        )
+        .unwrap();
+        for (variant, _) in &item.variants {
+            writeln!(out, "    {},", variant).unwrap();
+        }
+        out.push_str("}\n");
+
+        let expected: Vec<String> = item
+            .variants
+            .iter()
+            .map(|(_, value)| format!("`{}`", value))
+            .collect();
+        writeln!(
+            out,
+            "\nimpl {}::FromValue for {} {{",
+            config_path, item.name
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "    const EXPECTED: &'static str = {:?};\n",
+            format!("one of {}", expected.join(", "))
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "    fn from_value(value: &{}::Value) -> Option<Self> {{",
+            config_path
+        )
+        .unwrap();
+        out.push_str("        match value.as_str()?.trim() {\n");
+        for (variant, value) in &item.variants {
+            writeln!(
+                out,
+                "            {:?} => Some({}::{}),",
+                value, item.name, variant
+            )
+            .unwrap();
+        }
+        out.push_str("            _ => None,\n        }\n    }\n}\n");
+    }
+
+    writeln!(
+        out,
+        "\nimpl<'de> serde::Deserialize<'de> for Config {{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {{
+        let values = <{path}::Values as serde::Deserialize>::deserialize(deserializer)?;
+        let config = || -> Result<Self, {path}::ConfigurationError> {{
+            Ok(Config {{",
+        path = config_path
+    )
+    .unwrap();
+    for field in fields {
+        let value = match &field.presence {
+            Presence::Required => format!("values.required({:?})?", field.key),
+            Presence::Optional => format!("values.optional({:?})?", field.key),
+            Presence::Default(default) => {
+                format!("values.with_default({:?}, {:?})?", field.key, default)
+            }
+        };
+        writeln!(out, "                {}: {},", field.name, value).unwrap();
+    }
+    out.push_str(
+        "            })
+        };
+        config().map_err(serde::de::Error::custom)
+    }
+}
+",
+    );
+    out
+}
@@ -0,0 +1,23 @@
+use shopify_function_codegen::Codegen;
+
+const APIS: [&str; 3] = ["order_discounts", "product_discounts", "shipping_discounts"];
+
+fn main() {
+    let codegen = Codegen::default().scalars_path("crate::scalars");
+    for api in APIS {
+        codegen.write_output_types(
+            format!("schemas/2022-07/{}.graphql", api),
+            &format!("{}.rs", api),
+        );
+    }
+    codegen.write_enum_types(
+        "schemas/2022-07/product_discounts.graphql",
+        &["CurrencyCode"],
+        "currency_code.rs",
+    );
+    codegen.write_enum_types(
+        "schemas/2022-07/product_discounts.graphql",
+        &["LanguageCode"],
+        "language_code.rs",
+    );
+}
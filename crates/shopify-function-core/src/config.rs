@@ -0,0 +1,382 @@
+//! Loading the function configuration stored in the discount's metafield, and
+//! the string values of a checkout script's configuration.
+
+use std::fmt;
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+use serde::de::DeserializeOwned;
+pub use serde_json::Value;
+
+/// What to do when the configuration can't be used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Return the error, failing the function.
+    Strict,
+    /// Report the error on stderr and use the default configuration.
+    FailOpen,
+}
+
+#[derive(Debug)]
+pub enum ConfigurationError {
+    /// The metafield value isn't a valid configuration.
+    Parse(serde_json::Error),
+    /// A configuration value is outside of the range accepted by the API.
+    OutOfRange {
+        field: &'static str,
+        value: f64,
+        expected: &'static str,
+    },
+    /// A required configuration value is missing or empty.
+    MissingField(&'static str),
+    /// A configuration value can't be read as the type of its field.
+    InvalidField {
+        field: &'static str,
+        value: String,
+        expected: &'static str,
+    },
+}
+
+impl fmt::Display for ConfigurationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigurationError::Parse(error) => {
+                write!(
+                    f,
+                    "unable to parse configuration value from metafield: {}",
+                    error
+                )
+            }
+            ConfigurationError::OutOfRange {
+                field,
+                value,
+                expected,
+            } => write!(
+                f,
+                "configuration `{}` is {}, expected {}",
+                field, value, expected
+            ),
+            ConfigurationError::MissingField(field) => {
+                write!(f, "configuration `{}` is required", field)
+            }
+            ConfigurationError::InvalidField {
+                field,
+                value,
+                expected,
+            } => write!(
+                f,
+                "configuration `{}` is {}, expected {}",
+                field, value, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigurationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigurationError::Parse(error) => Some(error),
+            ConfigurationError::OutOfRange { .. }
+            | ConfigurationError::MissingField(_)
+            | ConfigurationError::InvalidField { .. } => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for ConfigurationError {
+    fn from(error: serde_json::Error) -> Self {
+        ConfigurationError::Parse(error)
+    }
+}
+
+/// Checks run on a configuration after it's parsed.
+pub trait Validate {
+    fn validate(&self) -> Result<(), ConfigurationError> {
+        Ok(())
+    }
+}
+
+/// Parses and validates the metafield `value`, using the default configuration
+/// when there's no metafield.
+pub fn load<C>(value: Option<&str>, mode: Mode) -> Result<C, ConfigurationError>
+where
+    C: DeserializeOwned + Validate + Default,
+{
+    let result = match value {
+        Some(value) => parse(value),
+        None => Ok(C::default()),
+    };
+    match (result, mode) {
+        (Err(error), Mode::FailOpen) => {
+            eprintln!("{}; using the default configuration", error);
+            Ok(C::default())
+        }
+        (result, _) => result,
+    }
+}
+
+fn parse<C: DeserializeOwned + Validate>(value: &str) -> Result<C, ConfigurationError> {
+    let configuration: C = serde_json::from_str(value)?;
+    configuration.validate()?;
+    Ok(configuration)
+}
+
+/// Checks that a percentage `value` is between 0 and 100.
+pub fn validate_percentage(field: &'static str, value: f64) -> Result<(), ConfigurationError> {
+    if (0.0..=100.0).contains(&value) {
+        Ok(())
+    } else {
+        Err(ConfigurationError::OutOfRange {
+            field,
+            value,
+            expected: "a percentage between 0 and 100",
+        })
+    }
+}
+
+/// Checks that an amount is greater than or equal to 0.
+pub fn validate_non_negative(field: &'static str, value: f64) -> Result<(), ConfigurationError> {
+    if value >= 0.0 && value.is_finite() {
+        Ok(())
+    } else {
+        Err(ConfigurationError::OutOfRange {
+            field,
+            value,
+            expected: "an amount greater than or equal to 0",
+        })
+    }
+}
+
+/// The values of a checkout script's configuration, which are all strings,
+/// read into the `Config` generated from its `script.config.yml`.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+#[serde(transparent)]
+pub struct Values(serde_json::Map<String, Value>);
+
+impl Values {
+    pub fn required<T: FromValue>(&self, field: &'static str) -> Result<T, ConfigurationError> {
+        self.optional(field)?
+            .ok_or(ConfigurationError::MissingField(field))
+    }
+
+    /// The value of `field`, or `None` if it's missing or empty.
+    pub fn optional<T: FromValue>(
+        &self,
+        field: &'static str,
+    ) -> Result<Option<T>, ConfigurationError> {
+        match self.0.get(field) {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::String(value)) if value.trim().is_empty() => Ok(None),
+            Some(value) => read(field, value).map(Some),
+        }
+    }
+
+    /// The value of `field`, or `default` if it's missing or empty.
+    pub fn with_default<T: FromValue>(
+        &self,
+        field: &'static str,
+        default: &str,
+    ) -> Result<T, ConfigurationError> {
+        match self.optional(field)? {
+            Some(value) => Ok(value),
+            None => read(field, &Value::String(default.to_string())),
+        }
+    }
+}
+
+fn read<T: FromValue>(field: &'static str, value: &Value) -> Result<T, ConfigurationError> {
+    T::from_value(value).ok_or_else(|| ConfigurationError::InvalidField {
+        field,
+        value: value.to_string(),
+        expected: T::EXPECTED,
+    })
+}
+
+/// A type a configuration value can be read as, from its string or its JSON
+/// value.
+pub trait FromValue: Sized {
+    /// What the value should look like, for error messages.
+    const EXPECTED: &'static str;
+
+    fn from_value(value: &Value) -> Option<Self>;
+}
+
+impl FromValue for String {
+    const EXPECTED: &'static str = "a string";
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::String(value) => Some(value.clone()),
+            Value::Number(_) | Value::Bool(_) => Some(value.to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for bool {
+    const EXPECTED: &'static str = "`true` or `false`";
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Bool(value) => Some(*value),
+            Value::String(value) => value.trim().parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for i64 {
+    const EXPECTED: &'static str = "an integer";
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Number(value) => value.as_i64(),
+            Value::String(value) => value.trim().parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for Decimal {
+    const EXPECTED: &'static str = "a decimal number";
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Number(value) => Decimal::from_str(&value.to_string()).ok(),
+            Value::String(value) => Decimal::from_str(value.trim()).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    const EXPECTED: &'static str = "a list";
+
+    /// Reads a JSON list, or a comma-separated string.
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Array(items) => items.iter().map(T::from_value).collect(),
+            Value::String(value) => match serde_json::from_str(value) {
+                Ok(Value::Array(items)) => items.iter().map(T::from_value).collect(),
+                _ => value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(|item| T::from_value(&Value::String(item.to_string())))
+                    .collect(),
+            },
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct Configuration {
+        value: f64,
+    }
+
+    impl Default for Configuration {
+        fn default() -> Self {
+            Configuration { value: 50.0 }
+        }
+    }
+
+    impl Validate for Configuration {
+        fn validate(&self) -> Result<(), ConfigurationError> {
+            validate_percentage("value", self.value)
+        }
+    }
+
+    #[test]
+    fn test_load_without_metafield() {
+        let configuration: Configuration = load(None, Mode::Strict).unwrap();
+
+        assert_eq!(configuration, Configuration::default());
+    }
+
+    #[test]
+    fn test_load_valid_configuration() {
+        let configuration: Configuration = load(Some(r#"{"value":10}"#), Mode::Strict).unwrap();
+
+        assert_eq!(configuration, Configuration { value: 10.0 });
+    }
+
+    #[test]
+    fn test_strict_mode_returns_errors() {
+        let error = load::<Configuration>(Some(r#"{"value":"10"#), Mode::Strict).unwrap_err();
+        assert!(matches!(error, ConfigurationError::Parse(_)));
+
+        let error = load::<Configuration>(Some(r#"{"value":150}"#), Mode::Strict).unwrap_err();
+        assert!(matches!(
+            error,
+            ConfigurationError::OutOfRange { field: "value", .. }
+        ));
+        assert_eq!(
+            error.to_string(),
+            "configuration `value` is 150, expected a percentage between 0 and 100"
+        );
+    }
+
+    #[test]
+    fn test_fail_open_mode_uses_default() {
+        let configuration: Configuration = load(Some("{"), Mode::FailOpen).unwrap();
+        assert_eq!(configuration, Configuration::default());
+
+        let configuration: Configuration = load(Some(r#"{"value":-1}"#), Mode::FailOpen).unwrap();
+        assert_eq!(configuration, Configuration::default());
+    }
+
+    #[test]
+    fn test_validate_ranges() {
+        assert!(validate_percentage("value", 0.0).is_ok());
+        assert!(validate_percentage("value", 100.0).is_ok());
+        assert!(validate_percentage("value", 100.5).is_err());
+        assert!(validate_percentage("value", f64::NAN).is_err());
+        assert!(validate_non_negative("amount", 0.0).is_ok());
+        assert!(validate_non_negative("amount", -0.01).is_err());
+        assert!(validate_non_negative("amount", f64::INFINITY).is_err());
+    }
+
+    #[test]
+    fn test_values_are_coerced() {
+        let values: Values = serde_json::from_str(
+            r#"{"threshold":"30.05","count":"3","enabled":"true","tags":"a, b","ids":"[1, 2]","empty":""}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            values.required::<Decimal>("threshold").unwrap(),
+            Decimal::new(3005, 2)
+        );
+        assert_eq!(values.required::<i64>("count").unwrap(), 3);
+        assert!(values.required::<bool>("enabled").unwrap());
+        assert_eq!(
+            values.required::<Vec<String>>("tags").unwrap(),
+            vec!["a", "b"]
+        );
+        assert_eq!(values.required::<Vec<i64>>("ids").unwrap(), vec![1, 2]);
+        assert_eq!(values.optional::<String>("empty").unwrap(), None);
+        assert_eq!(values.optional::<String>("missing").unwrap(), None);
+        assert_eq!(
+            values.with_default::<String>("missing", "CAD").unwrap(),
+            "CAD"
+        );
+    }
+
+    #[test]
+    fn test_values_errors() {
+        let values: Values = serde_json::from_str(r#"{"threshold":"abc","empty":" "}"#).unwrap();
+
+        let error = values.required::<Decimal>("threshold").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            r#"configuration `threshold` is "abc", expected a decimal number"#
+        );
+        let error = values.required::<String>("empty").unwrap_err();
+        assert_eq!(error.to_string(), "configuration `empty` is required");
+    }
+}
@@ -0,0 +1,278 @@
+//! Golden-file tests: every `<name>.input.json` in a directory is run through
+//! the function and its result compared with `<name>.output.json`, so cases
+//! can be added without writing Rust.
+//!
+//! A function error, or an input the function can't read, is compared as
+//! `{ "error": "<message>" }`. Numbers are
+//! compared by value, `10` matches `10.0`. Run the tests with `BLESS=1` to
+//! write the current results as the expected outputs.
+//!
+//! ```ignore
+//! #[test]
+//! fn test_fixtures() {
+//!     shopify_function_core::fixtures::check("tests/fixtures", function);
+//! }
+//! ```
+
+use std::fmt::{self, Display, Write};
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use similar::TextDiff;
+
+/// A fixture whose result doesn't match its expected output.
+#[derive(Debug)]
+pub struct Failure {
+    pub name: String,
+    pub message: String,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.message)
+    }
+}
+
+/// Runs the fixtures in `dir`, panicking with a diff of every mismatch.
+pub fn check<I, O, E, F>(dir: impl AsRef<Path>, function: F)
+where
+    I: DeserializeOwned,
+    O: Serialize,
+    E: Display,
+    F: FnMut(I) -> Result<O, E>,
+{
+    let bless = std::env::var_os("BLESS").is_some_and(|value| value == "1");
+    let failures = check_with(dir.as_ref(), bless, function);
+    if !failures.is_empty() {
+        let mut message = String::new();
+        for failure in &failures {
+            writeln!(message, "{}", failure).unwrap();
+        }
+        panic!(
+            "{} fixture(s) failed, run with BLESS=1 to update the expected outputs\n\n{}",
+            failures.len(),
+            message
+        );
+    }
+}
+
+/// Runs the fixtures in `dir`, writing the results as the expected outputs if
+/// `bless` is set.
+pub fn check_with<I, O, E, F>(dir: &Path, bless: bool, mut function: F) -> Vec<Failure>
+where
+    I: DeserializeOwned,
+    O: Serialize,
+    E: Display,
+    F: FnMut(I) -> Result<O, E>,
+{
+    let inputs = match inputs(dir) {
+        Ok(inputs) if !inputs.is_empty() => inputs,
+        Ok(_) => return vec![failure(dir, "no `*.input.json` fixtures")],
+        Err(error) => return vec![failure(dir, error)],
+    };
+
+    let mut failures = Vec::new();
+    for (name, input_path) in inputs {
+        let fail = |message: String| Failure {
+            name: name.clone(),
+            message,
+        };
+        let input = match read_json(&input_path) {
+            Ok(input) => input,
+            Err(error) => {
+                failures.push(fail(error));
+                continue;
+            }
+        };
+        let actual = match serde_json::from_value(input) {
+            Ok(input) => match function(input) {
+                Ok(output) => serde_json::to_value(&output)
+                    .unwrap_or_else(|error| panic!("unable to serialize the result: {}", error)),
+                Err(error) => serde_json::json!({ "error": error.to_string() }),
+            },
+            Err(error) => serde_json::json!({
+                "error": format!("unable to read the input: {}", error)
+            }),
+        };
+
+        let output_path = dir.join(format!("{}.output.json", name));
+        if bless {
+            let mut output = serde_json::to_string_pretty(&actual).unwrap();
+            output.push('\n');
+            if let Err(error) = std::fs::write(&output_path, output) {
+                failures.push(fail(format!("{}: {}", output_path.display(), error)));
+            }
+            continue;
+        }
+        if !output_path.exists() {
+            failures.push(fail(format!("{} is missing", output_path.display())));
+            continue;
+        }
+        match read_json(&output_path) {
+            Ok(expected) if json_eq(&expected, &actual) => {}
+            Ok(expected) => failures.push(fail(diff(&expected, &actual))),
+            Err(error) => failures.push(fail(error)),
+        }
+    }
+    failures
+}
+
+/// The fixture names and input paths in `dir`, sorted by name.
+fn inputs(dir: &Path) -> Result<Vec<(String, PathBuf)>, String> {
+    let entries = std::fs::read_dir(dir).map_err(|error| error.to_string())?;
+    let mut inputs = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|error| error.to_string())?.path();
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".input.json"))
+            .map(str::to_string);
+        if let Some(name) = name {
+            inputs.push((name, path));
+        }
+    }
+    inputs.sort();
+    Ok(inputs)
+}
+
+fn failure(dir: &Path, message: impl Display) -> Failure {
+    Failure {
+        name: dir.display().to_string(),
+        message: message.to_string(),
+    }
+}
+
+fn read_json(path: &Path) -> Result<Value, String> {
+    let source =
+        std::fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+    serde_json::from_str(&source).map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+/// Whether two values are equal, comparing numbers by value.
+fn json_eq(expected: &Value, actual: &Value) -> bool {
+    match (expected, actual) {
+        (Value::Number(expected), Value::Number(actual)) => expected.as_f64() == actual.as_f64(),
+        (Value::Array(expected), Value::Array(actual)) => {
+            expected.len() == actual.len()
+                && expected.iter().zip(actual).all(|(e, a)| json_eq(e, a))
+        }
+        (Value::Object(expected), Value::Object(actual)) => {
+            expected.len() == actual.len()
+                && expected
+                    .iter()
+                    .all(|(key, e)| actual.get(key).is_some_and(|a| json_eq(e, a)))
+        }
+        (expected, actual) => expected == actual,
+    }
+}
+
+fn diff(expected: &Value, actual: &Value) -> String {
+    let expected = serde_json::to_string_pretty(expected).unwrap() + "\n";
+    let actual = serde_json::to_string_pretty(actual).unwrap() + "\n";
+    let diff = TextDiff::from_lines(&expected, &actual)
+        .unified_diff()
+        .header("expected", "actual")
+        .to_string();
+    format!("the result doesn't match the expected output\n{}", diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(serde::Deserialize, serde::Serialize)]
+    struct Message {
+        value: i32,
+    }
+
+    fn double(input: Message) -> Result<Message, String> {
+        if input.value < 0 {
+            return Err("negative value".to_string());
+        }
+        Ok(Message {
+            value: input.value * 2,
+        })
+    }
+
+    fn fixtures(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "shopify-function-core-fixtures-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for (file, contents) in files {
+            std::fs::write(dir.join(file), contents).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_matching_fixtures() {
+        let dir = fixtures(
+            "matching",
+            &[
+                ("double.input.json", r#"{"value": 21, "ignored": true}"#),
+                ("double.output.json", r#"{"value": 42.0}"#),
+                ("negative.input.json", r#"{"value": -1}"#),
+                ("negative.output.json", r#"{"error": "negative value"}"#),
+                ("README.md", "Not a fixture"),
+            ],
+        );
+
+        assert!(check_with(&dir, false, double).is_empty());
+    }
+
+    #[test]
+    fn test_mismatches() {
+        let dir = fixtures(
+            "mismatches",
+            &[
+                ("a.input.json", r#"{"value": 1}"#),
+                ("a.output.json", r#"{"value": 3}"#),
+                ("b.input.json", r#"{"value": 1}"#),
+                ("c.input.json", r#"{"value": "1"}"#),
+                ("c.output.json", r#"{"error": "unable to read the input"}"#),
+            ],
+        );
+
+        let failures = check_with(&dir, false, double);
+        let names: Vec<&str> = failures.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["a", "b", "c"]);
+        assert_eq!(
+            failures[0].message,
+            "the result doesn't match the expected output\n--- expected\n+++ actual\n@@ -1,3 +1,3 @@\n {\n-  \"value\": 3\n+  \"value\": 2\n }\n"
+        );
+        assert!(failures[1].message.ends_with("b.output.json is missing"));
+        assert!(failures[2]
+            .message
+            .contains("+  \"error\": \"unable to read the input: invalid type: string \\\"1\\\", expected i32\""));
+        assert_eq!(
+            check_with(&fixtures("empty", &[]), false, double)[0].message,
+            "no `*.input.json` fixtures"
+        );
+    }
+
+    #[test]
+    fn test_bless() {
+        let dir = fixtures(
+            "bless",
+            &[
+                ("a.input.json", r#"{"value": 1}"#),
+                ("a.output.json", r#"{"value": 3}"#),
+                ("b.input.json", r#"{"value": 2}"#),
+            ],
+        );
+
+        assert!(check_with(&dir, true, double).is_empty());
+        assert_eq!(
+            std::fs::read_to_string(dir.join("b.output.json")).unwrap(),
+            "{\n  \"value\": 4\n}\n"
+        );
+        assert!(check_with(&dir, false, double).is_empty());
+    }
+}
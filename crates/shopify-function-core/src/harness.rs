@@ -0,0 +1,103 @@
+//! The fuzz targets and property tests every function shares, so each one
+//! only says how to call its function and what inputs to generate.
+//!
+//! ```ignore
+//! fuzz_target!(|data: &[u8]| harness::fuzz(Format::Json, data, function::function));
+//! ```
+
+use std::error::Error;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::run::{run_with, Format, RunError};
+
+/// Runs `function` on `data` like its entry point does. The function may
+/// reject the input, but panicking or producing a result that doesn't
+/// serialize fails the fuzz target.
+pub fn fuzz<I, O, E, F>(format: Format, data: &[u8], function: F)
+where
+    I: DeserializeOwned,
+    O: Serialize,
+    E: Into<Box<dyn Error>>,
+    F: FnOnce(I) -> Result<O, E>,
+{
+    if let Err(RunError::Output(error)) = run_with(format, data, std::io::sink(), function) {
+        panic!("unable to write the output: {}", error);
+    }
+}
+
+/// Runs `function` on random `inputs`, panicking with the smallest failing
+/// input if it panics, fails or returns a result that doesn't match `schema`.
+///
+/// ```ignore
+/// #[test]
+/// fn test_results_are_valid() {
+///     harness::check(include_str!("../schema.graphql"), input(), function);
+/// }
+/// ```
+#[cfg(feature = "validate")]
+pub fn check<I, O, E, F>(
+    schema: &str,
+    inputs: impl proptest::strategy::Strategy<Value = I>,
+    function: F,
+) where
+    I: std::fmt::Debug,
+    O: Serialize,
+    E: std::fmt::Display,
+    F: Fn(I) -> Result<O, E>,
+{
+    use proptest::test_runner::{TestCaseError, TestRunner};
+
+    let result = TestRunner::default().run(&inputs, |input| match function(input) {
+        Ok(result) => crate::validate::validate(schema, &result)
+            .map_err(|error| TestCaseError::fail(error.to_string())),
+        Err(error) => Err(TestCaseError::fail(error.to_string())),
+    });
+    if let Err(error) = result {
+        panic!("{}", error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn double(value: i32) -> Result<i32, String> {
+        value.checked_mul(2).ok_or_else(|| "too large".to_string())
+    }
+
+    #[test]
+    fn test_fuzz() {
+        fuzz(Format::Json, b"21", double);
+        fuzz(Format::Json, b"not json", double);
+        fuzz(Format::Json, b"2147483647", double);
+    }
+
+    #[test]
+    #[should_panic(expected = "panicked")]
+    fn test_fuzz_panics() {
+        fuzz(Format::Json, b"1", |_: i32| -> Result<i32, String> {
+            panic!("panicked")
+        });
+    }
+
+    #[cfg(feature = "validate")]
+    #[test]
+    #[should_panic(expected = "FunctionResult.discounts[0].value.percentage.value: must be >= 0")]
+    fn test_check() {
+        let schema = include_str!("../schemas/2022-07/product_discounts.graphql");
+        let result = |value: f64| {
+            Ok::<_, String>(serde_json::json!({
+                "discountApplicationStrategy": "FIRST",
+                "discounts": [{
+                    "targets": [{ "productVariant": { "id": "gid://shopify/ProductVariant/1" } }],
+                    "value": { "percentage": { "value": value } },
+                }],
+            }))
+        };
+
+        check(schema, 0.0..100.0f64, result);
+        check(schema, -10.0..100.0f64, result);
+    }
+}
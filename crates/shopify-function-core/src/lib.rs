@@ -0,0 +1,36 @@
+//! Types shared by the Rust Shopify Functions in this repository.
+//!
+//! Each discount API gets its own module with the `FunctionResult` types
+//! generated from its schema in `schemas/`, so the functions depend on one copy
+//! of the output types instead of carrying their own. [`config`] loads and
+//! validates the configuration merchants store in the discount's metafield,
+//! [`Money`] does exact, currency-aware arithmetic on amounts and [`messages`]
+//! picks the discount message in the buyer's language. [`run`] is the
+//! `main` of every function. The `validate` feature checks results against the
+//! schema, in tests and in debug builds of functions. For tests, the `fixtures`
+//! feature runs golden-file tests and the `proptest` feature provides
+//! strategies for property tests and the [`harness`] of fuzz targets.
+
+pub mod config;
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
+#[cfg(feature = "proptest")]
+pub mod harness;
+pub mod messages;
+pub mod money;
+pub mod order_discounts;
+pub mod product_discounts;
+pub mod run;
+pub mod scalars;
+pub mod shipping_discounts;
+#[cfg(feature = "proptest")]
+pub mod strategies;
+#[cfg(feature = "validate")]
+pub mod validate;
+
+pub use money::Money;
+pub use run::run;
+pub use scalars::*;
+
+/// The `api_version` of the schemas these types were written against.
+pub const API_VERSION: &str = "2022-07";
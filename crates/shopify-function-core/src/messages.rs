@@ -0,0 +1,167 @@
+//! Discount messages in the buyer's language, from templates with
+//! placeholders like `{percent}` filled by the function.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{self, ConfigurationError};
+
+include!(concat!(env!("OUT_DIR"), "/language_code.rs"));
+
+impl fmt::Display for LanguageCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::String(code)) => f.write_str(&code),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+/// A message template per language, and the language used for buyers whose
+/// language has no template.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Messages {
+    #[serde(default)]
+    pub templates: HashMap<LanguageCode, String>,
+    #[serde(default = "default_fallback_language")]
+    pub fallback_language: LanguageCode,
+}
+
+fn default_fallback_language() -> LanguageCode {
+    LanguageCode::En
+}
+
+impl Default for Messages {
+    fn default() -> Self {
+        Messages {
+            templates: HashMap::new(),
+            fallback_language: default_fallback_language(),
+        }
+    }
+}
+
+impl Messages {
+    /// The template in `language`, or in the fallback language.
+    pub fn template(&self, language: LanguageCode) -> Option<&str> {
+        self.templates
+            .get(&language)
+            .or_else(|| self.templates.get(&self.fallback_language))
+            .map(String::as_str)
+    }
+
+    /// The message in `language` with its placeholders filled from `values`,
+    /// or `None` when there's no template or it renders blank.
+    pub fn render(&self, language: LanguageCode, values: &[(&str, String)]) -> Option<String> {
+        let message = interpolate(self.template(language)?, values);
+        (!message.trim().is_empty()).then_some(message)
+    }
+}
+
+impl config::Validate for Messages {
+    fn validate(&self) -> Result<(), ConfigurationError> {
+        if self.templates.is_empty() || self.templates.contains_key(&self.fallback_language) {
+            Ok(())
+        } else {
+            Err(ConfigurationError::InvalidField {
+                field: "messages.fallbackLanguage",
+                value: self.fallback_language.to_string(),
+                expected: "a language with a template",
+            })
+        }
+    }
+}
+
+/// Replaces each `{name}` in `template` with its value. Unknown placeholders
+/// are left as they are.
+pub fn interpolate(template: &str, values: &[(&str, String)]) -> String {
+    let mut message = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        message.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = rest.find('}').and_then(|end| {
+            let name = &rest[1..end];
+            values
+                .iter()
+                .find(|(placeholder, _)| *placeholder == name)
+                .map(|(_, value)| (value, end))
+        });
+        match value {
+            Some((value, end)) => {
+                message.push_str(value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                message.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    message.push_str(rest);
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Validate;
+
+    fn messages() -> Messages {
+        serde_json::from_str(
+            r#"{
+                "templates": { "EN": "{percent}% off", "FR": "{percent} % de réduction" },
+                "fallbackLanguage": "EN"
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_render_in_buyer_language() {
+        let values = [("percent", "10".to_string())];
+
+        assert_eq!(
+            messages().render(LanguageCode::Fr, &values).as_deref(),
+            Some("10 % de réduction")
+        );
+        assert_eq!(
+            messages().render(LanguageCode::De, &values).as_deref(),
+            Some("10% off")
+        );
+        assert_eq!(Messages::default().render(LanguageCode::En, &values), None);
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let values = [
+            ("amount", "5.00".to_string()),
+            ("currency", "CAD".to_string()),
+        ];
+
+        assert_eq!(
+            interpolate("{amount} {currency} off", &values),
+            "5.00 CAD off"
+        );
+        assert_eq!(
+            interpolate("{unknown} {amount}, {", &values),
+            "{unknown} 5.00, {"
+        );
+        assert_eq!(interpolate("{{amount}}", &values), "{5.00}");
+    }
+
+    #[test]
+    fn test_fallback_language_needs_a_template() {
+        let mut messages = messages();
+        assert!(messages.validate().is_ok());
+
+        messages.fallback_language = LanguageCode::De;
+        assert_eq!(
+            messages.validate().unwrap_err().to_string(),
+            "configuration `messages.fallbackLanguage` is DE, expected a language with a template"
+        );
+        assert!(Messages::default().validate().is_ok());
+    }
+}
@@ -0,0 +1,426 @@
+//! Amounts of money, with exact decimal arithmetic.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::RoundingStrategy;
+use serde::de::IntoDeserializer;
+use serde::{Deserialize, Serialize};
+
+use crate::scalars::Decimal;
+
+include!(concat!(env!("OUT_DIR"), "/currency_code.rs"));
+
+impl CurrencyCode {
+    /// The number of decimals of the currency's minor unit, from ISO 4217.
+    pub fn minor_units(self) -> u32 {
+        use CurrencyCode::*;
+        match self {
+            Bif | Clp | Djf | Gnf | Isk | Jpy | Kmf | Krw | Pyg | Rwf | Ugx | Vnd | Vuv | Xaf
+            | Xof | Xpf => 0,
+            Bhd | Iqd | Jod | Kwd | Lyd | Omr | Tnd => 3,
+            _ => 2,
+        }
+    }
+}
+
+impl FromStr for CurrencyCode {
+    type Err = serde::de::value::Error;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        CurrencyCode::deserialize(code.into_deserializer())
+    }
+}
+
+impl fmt::Display for CurrencyCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::String(code)) => f.write_str(&code),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MoneyError {
+    /// The amounts are in different currencies.
+    CurrencyMismatch {
+        expected: CurrencyCode,
+        found: CurrencyCode,
+    },
+    /// The result doesn't fit in a `Decimal`.
+    Overflow,
+    /// The amount isn't a valid `Decimal`.
+    Parse(String),
+}
+
+impl fmt::Display for MoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoneyError::CurrencyMismatch { expected, found } => {
+                write!(f, "expected an amount in {}, found {}", expected, found)
+            }
+            MoneyError::Overflow => f.write_str("amount overflowed"),
+            MoneyError::Parse(amount) => write!(f, "`{}` is not a decimal amount", amount),
+        }
+    }
+}
+
+impl std::error::Error for MoneyError {}
+
+/// How amounts converted from the shop's currency are rounded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Rounding {
+    /// To the currency's minor unit, half away from zero.
+    #[default]
+    MinorUnit,
+    /// To the nearest whole unit, e.g. `13.40` becomes `13`.
+    WholeUnit,
+    /// Up to the next amount ending in `.99`, e.g. `13.40` becomes `13.99`.
+    /// Currencies without a minor unit round to whole units instead.
+    NinetyNine,
+}
+
+/// The currency the buyer pays in and its `presentmentCurrencyRate`, to
+/// convert amounts configured in the shop's currency.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Presentment {
+    pub currency_code: CurrencyCode,
+    /// Units of the presentment currency per unit of the shop's currency.
+    pub rate: Decimal,
+}
+
+impl Presentment {
+    /// Without a rate, the buyer pays in the shop's currency.
+    pub fn new(currency_code: CurrencyCode, rate: Option<Decimal>) -> Self {
+        Presentment {
+            currency_code,
+            rate: rate.unwrap_or(Decimal::ONE),
+        }
+    }
+
+    /// Converts an amount in the shop's currency. Amounts that aren't
+    /// converted, at a rate of 1, are left as configured.
+    pub fn convert(&self, amount: Decimal, rounding: Rounding) -> Result<Money, MoneyError> {
+        let money = Money::new(amount, self.currency_code);
+        if self.rate == Decimal::ONE {
+            return Ok(money);
+        }
+        Ok(money.checked_mul(self.rate)?.round_with(rounding))
+    }
+}
+
+/// An amount in a currency, deserialized from a `MoneyV2`.
+///
+/// Amounts in different currencies can't be added or compared.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Money {
+    pub amount: Decimal,
+    pub currency_code: CurrencyCode,
+}
+
+impl Money {
+    pub fn new(amount: Decimal, currency_code: CurrencyCode) -> Self {
+        Money {
+            amount,
+            currency_code,
+        }
+    }
+
+    pub fn zero(currency_code: CurrencyCode) -> Self {
+        Money::new(Decimal::ZERO, currency_code)
+    }
+
+    /// Parses a `Decimal` scalar, e.g. `"29.99"`.
+    pub fn parse(amount: &str, currency_code: CurrencyCode) -> Result<Self, MoneyError> {
+        let amount =
+            Decimal::from_str_exact(amount).map_err(|_| MoneyError::Parse(amount.to_string()))?;
+        Ok(Money::new(amount, currency_code))
+    }
+
+    /// An amount in the currency's minor unit, e.g. cents.
+    pub fn from_subunits(subunits: i64, currency_code: CurrencyCode) -> Self {
+        Money::new(
+            Decimal::new(subunits, currency_code.minor_units()),
+            currency_code,
+        )
+    }
+
+    /// The amount in the currency's minor unit, rounded half away from zero.
+    pub fn subunits(&self) -> Option<i64> {
+        let scale = Decimal::from(10_i64.pow(self.currency_code.minor_units()));
+        self.amount.checked_mul(scale)?.round().to_i64()
+    }
+
+    /// Rounds the amount to the currency's minor unit, half away from zero.
+    pub fn round(&self) -> Self {
+        let amount = self.amount.round_dp_with_strategy(
+            self.currency_code.minor_units(),
+            RoundingStrategy::MidpointAwayFromZero,
+        );
+        Money::new(amount, self.currency_code)
+    }
+
+    /// Rounds the amount with `rounding`, e.g. to a price ending in `.99`.
+    pub fn round_with(&self, rounding: Rounding) -> Self {
+        let whole = |amount: Decimal| {
+            amount.round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero)
+        };
+        let amount = match rounding {
+            Rounding::MinorUnit => return self.round(),
+            Rounding::WholeUnit => whole(self.amount),
+            Rounding::NinetyNine if self.currency_code.minor_units() == 0 => whole(self.amount),
+            Rounding::NinetyNine if self.amount.is_zero() => self.amount,
+            Rounding::NinetyNine => {
+                let cent = Decimal::new(1, 2);
+                (self.amount + cent).ceil() - cent
+            }
+        };
+        Money::new(amount, self.currency_code)
+    }
+
+    pub fn checked_add(&self, other: &Money) -> Result<Self, MoneyError> {
+        self.check_currency(other)?;
+        let amount = self
+            .amount
+            .checked_add(other.amount)
+            .ok_or(MoneyError::Overflow)?;
+        Ok(Money::new(amount, self.currency_code))
+    }
+
+    pub fn checked_sub(&self, other: &Money) -> Result<Self, MoneyError> {
+        self.check_currency(other)?;
+        let amount = self
+            .amount
+            .checked_sub(other.amount)
+            .ok_or(MoneyError::Overflow)?;
+        Ok(Money::new(amount, self.currency_code))
+    }
+
+    pub fn checked_mul(&self, factor: Decimal) -> Result<Self, MoneyError> {
+        let amount = self
+            .amount
+            .checked_mul(factor)
+            .ok_or(MoneyError::Overflow)?;
+        Ok(Money::new(amount, self.currency_code))
+    }
+
+    /// Adds up `amounts`, which must all be in `currency_code`.
+    pub fn sum<'a>(
+        currency_code: CurrencyCode,
+        amounts: impl IntoIterator<Item = &'a Money>,
+    ) -> Result<Self, MoneyError> {
+        amounts
+            .into_iter()
+            .try_fold(Money::zero(currency_code), |total, amount| {
+                total.checked_add(amount)
+            })
+    }
+
+    fn check_currency(&self, other: &Money) -> Result<(), MoneyError> {
+        if self.currency_code == other.currency_code {
+            Ok(())
+        } else {
+            Err(MoneyError::CurrencyMismatch {
+                expected: self.currency_code,
+                found: other.currency_code,
+            })
+        }
+    }
+}
+
+impl PartialOrd for Money {
+    /// Amounts in different currencies aren't comparable.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.currency_code == other.currency_code {
+            self.amount.partial_cmp(&other.amount)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.amount, self.currency_code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn money(amount: &str, currency_code: CurrencyCode) -> Money {
+        Money::parse(amount, currency_code).unwrap()
+    }
+
+    #[test]
+    fn test_deserialize_money_v2() {
+        let money: Money =
+            serde_json::from_str(r#"{ "amount": "29.99", "currencyCode": "CAD" }"#).unwrap();
+
+        assert_eq!(money, Money::from_subunits(2999, CurrencyCode::Cad));
+        assert_eq!(
+            serde_json::json!(money),
+            serde_json::json!({ "amount": "29.99", "currencyCode": "CAD" })
+        );
+    }
+
+    #[test]
+    fn test_minor_units() {
+        assert_eq!(CurrencyCode::Jpy.minor_units(), 0);
+        assert_eq!(CurrencyCode::Kwd.minor_units(), 3);
+        assert_eq!(CurrencyCode::Usd.minor_units(), 2);
+
+        assert_eq!(
+            Money::from_subunits(500, CurrencyCode::Jpy),
+            money("500", CurrencyCode::Jpy)
+        );
+        assert_eq!(
+            Money::from_subunits(1500, CurrencyCode::Kwd),
+            money("1.5", CurrencyCode::Kwd)
+        );
+        assert_eq!(money("19.995", CurrencyCode::Usd).subunits(), Some(2000));
+        assert_eq!(money("19.995", CurrencyCode::Kwd).subunits(), Some(19995));
+        assert_eq!(
+            money("19.5", CurrencyCode::Jpy).round().to_string(),
+            "20 JPY"
+        );
+    }
+
+    #[test]
+    fn test_exact_arithmetic() {
+        let total = money("0.1", CurrencyCode::Usd)
+            .checked_add(&money("0.2", CurrencyCode::Usd))
+            .unwrap();
+        assert_eq!(total, money("0.3", CurrencyCode::Usd));
+
+        let total = money("19.99", CurrencyCode::Usd)
+            .checked_mul(Decimal::from(3))
+            .unwrap();
+        assert_eq!(total.to_string(), "59.97 USD");
+
+        let amounts = [
+            money("1.25", CurrencyCode::Eur),
+            money("2.5", CurrencyCode::Eur),
+        ];
+        assert_eq!(
+            Money::sum(CurrencyCode::Eur, &amounts).unwrap(),
+            money("3.75", CurrencyCode::Eur)
+        );
+    }
+
+    #[test]
+    fn test_mismatched_currencies() {
+        let cad = money("10.00", CurrencyCode::Cad);
+        let usd = money("10.00", CurrencyCode::Usd);
+
+        assert_eq!(
+            cad.checked_add(&usd),
+            Err(MoneyError::CurrencyMismatch {
+                expected: CurrencyCode::Cad,
+                found: CurrencyCode::Usd,
+            })
+        );
+        assert_eq!(
+            cad.checked_add(&usd).unwrap_err().to_string(),
+            "expected an amount in CAD, found USD"
+        );
+        assert!(cad.checked_sub(&usd).is_err());
+        assert_eq!(cad.partial_cmp(&usd), None);
+        assert!(cad > money("9.99", CurrencyCode::Cad));
+    }
+
+    #[test]
+    fn test_rounding() {
+        let rounded = |amount: &str, currency_code, rounding| {
+            money(amount, currency_code)
+                .round_with(rounding)
+                .to_string()
+        };
+
+        assert_eq!(
+            rounded("13.405", CurrencyCode::Eur, Rounding::MinorUnit),
+            "13.41 EUR"
+        );
+        assert_eq!(
+            rounded("13.40", CurrencyCode::Eur, Rounding::WholeUnit),
+            "13 EUR"
+        );
+        assert_eq!(
+            rounded("13.50", CurrencyCode::Eur, Rounding::WholeUnit),
+            "14 EUR"
+        );
+        assert_eq!(
+            rounded("13.40", CurrencyCode::Eur, Rounding::NinetyNine),
+            "13.99 EUR"
+        );
+        assert_eq!(
+            rounded("13.00", CurrencyCode::Eur, Rounding::NinetyNine),
+            "13.99 EUR"
+        );
+        assert_eq!(
+            rounded("12.99", CurrencyCode::Eur, Rounding::NinetyNine),
+            "12.99 EUR"
+        );
+        assert_eq!(
+            rounded("0", CurrencyCode::Eur, Rounding::NinetyNine),
+            "0 EUR"
+        );
+        assert_eq!(
+            rounded("1349.6", CurrencyCode::Jpy, Rounding::NinetyNine),
+            "1350 JPY"
+        );
+    }
+
+    #[test]
+    fn test_presentment_conversion() {
+        let convert = |currency_code, rate: &str, rounding| {
+            Presentment::new(currency_code, Some(rate.parse().unwrap()))
+                .convert(Decimal::from(10), rounding)
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(
+            convert(CurrencyCode::Cad, "1.3456", Rounding::MinorUnit),
+            "13.46 CAD"
+        );
+        assert_eq!(
+            convert(CurrencyCode::Eur, "0.9123", Rounding::NinetyNine),
+            "9.99 EUR"
+        );
+        assert_eq!(
+            convert(CurrencyCode::Jpy, "134.567", Rounding::MinorUnit),
+            "1346 JPY"
+        );
+        assert_eq!(
+            convert(CurrencyCode::Kwd, "0.30712", Rounding::MinorUnit),
+            "3.071 KWD"
+        );
+        assert_eq!(
+            convert(CurrencyCode::Usd, "1", Rounding::NinetyNine),
+            "10 USD"
+        );
+        assert_eq!(
+            Presentment::new(CurrencyCode::Usd, None)
+                .convert(Decimal::new(1050, 2), Rounding::WholeUnit)
+                .unwrap()
+                .to_string(),
+            "10.50 USD"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Money::parse("ten", CurrencyCode::Usd),
+            Err(MoneyError::Parse("ten".to_string()))
+        );
+        assert_eq!("CAD".parse::<CurrencyCode>().unwrap(), CurrencyCode::Cad);
+    }
+}
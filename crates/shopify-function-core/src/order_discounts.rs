@@ -0,0 +1,3 @@
+//! Output types for the `order_discounts` API.
+
+include!(concat!(env!("OUT_DIR"), "/order_discounts.rs"));
@@ -0,0 +1,41 @@
+//! Output types for the `product_discounts` API.
+
+include!(concat!(env!("OUT_DIR"), "/product_discounts.rs"));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_amount_omits_unset_applies_to_each_item() {
+        let value = Value::FixedAmount(FixedAmount {
+            amount: Decimal::new(50, 1),
+            applies_to_each_item: None,
+        });
+
+        assert_eq!(
+            serde_json::json!(value),
+            serde_json::json!({ "fixedAmount": { "amount": "5.0" } })
+        );
+    }
+
+    #[test]
+    fn test_condition_serializes_as_one_of() {
+        let condition = Condition::ProductMinimumQuantity(ProductMinimumQuantity {
+            ids: vec!["gid://shopify/ProductVariant/0".to_string()],
+            minimum_quantity: 3,
+            target_type: TargetType::ProductVariant,
+        });
+
+        assert_eq!(
+            serde_json::json!(condition),
+            serde_json::json!({
+                "productMinimumQuantity": {
+                    "ids": ["gid://shopify/ProductVariant/0"],
+                    "minimumQuantity": 3,
+                    "targetType": "PRODUCT_VARIANT",
+                }
+            })
+        );
+    }
+}
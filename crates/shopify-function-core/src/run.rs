@@ -0,0 +1,278 @@
+//! The entry point of a function: reads the input from stdin, calls the
+//! function and writes its result to stdout.
+//!
+//! Functions speak JSON. Checkout scripts that speak MessagePack enable the
+//! `messagepack` feature, which makes it the default [`Format`].
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufWriter, Read, Write};
+use std::process::ExitCode;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// The wire format of the input and output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    /// Structs are encoded as maps, keyed by field name.
+    #[cfg(feature = "messagepack")]
+    MessagePack,
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        #[cfg(feature = "messagepack")]
+        return Format::MessagePack;
+        #[cfg(not(feature = "messagepack"))]
+        return Format::Json;
+    }
+}
+
+impl Format {
+    fn decode<I: DeserializeOwned>(self, input: &[u8]) -> Result<I, Box<dyn Error>> {
+        match self {
+            Format::Json => Ok(serde_json::from_slice(input)?),
+            #[cfg(feature = "messagepack")]
+            Format::MessagePack => Ok(rmp_serde::from_slice(input)?),
+        }
+    }
+
+    fn encode<O: Serialize>(self, writer: impl Write, output: &O) -> Result<(), Box<dyn Error>> {
+        match self {
+            Format::Json => serde_json::to_writer(writer, output)?,
+            #[cfg(feature = "messagepack")]
+            Format::MessagePack => {
+                output.serialize(&mut rmp_serde::Serializer::new(writer).with_struct_map())?
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where running a function failed.
+#[derive(Debug)]
+pub enum RunError {
+    Input(Box<dyn Error>),
+    Function(Box<dyn Error>),
+    Output(Box<dyn Error>),
+}
+
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RunError::Input(error) => write!(f, "unable to read the input: {}", error),
+            RunError::Function(error) => write!(f, "{}", error),
+            RunError::Output(error) => write!(f, "unable to write the output: {}", error),
+        }
+    }
+}
+
+impl Error for RunError {}
+
+/// Runs `function` on the input read from stdin in the default [`Format`],
+/// reporting errors on stderr.
+///
+/// ```ignore
+/// fn main() -> std::process::ExitCode {
+///     shopify_function_core::run(function)
+/// }
+/// ```
+pub fn run<I, O, E, F>(function: F) -> ExitCode
+where
+    I: DeserializeOwned,
+    O: Serialize,
+    E: Into<Box<dyn Error>>,
+    F: FnOnce(I) -> Result<O, E>,
+{
+    exit_code(run_with(
+        Format::default(),
+        io::stdin().lock(),
+        io::stdout().lock(),
+        function,
+    ))
+}
+
+/// Runs `function` like [`run`], checking its result against `schema` in debug
+/// builds so results the API would reject fail locally.
+///
+/// ```ignore
+/// fn main() -> std::process::ExitCode {
+///     shopify_function_core::run::run_validated(include_str!("../schema.graphql"), function)
+/// }
+/// ```
+#[cfg(feature = "validate")]
+pub fn run_validated<I, O, E, F>(schema: &str, function: F) -> ExitCode
+where
+    I: DeserializeOwned,
+    O: Serialize,
+    E: Into<Box<dyn Error>>,
+    F: FnOnce(I) -> Result<O, E>,
+{
+    exit_code(run_validated_with(
+        schema,
+        Format::default(),
+        io::stdin().lock(),
+        io::stdout().lock(),
+        function,
+    ))
+}
+
+fn exit_code(result: Result<(), RunError>) -> ExitCode {
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Runs `function` on the input read from `reader`, writing its result to `writer`.
+pub fn run_with<I, O, E, F>(
+    format: Format,
+    reader: impl Read,
+    writer: impl Write,
+    function: F,
+) -> Result<(), RunError>
+where
+    I: DeserializeOwned,
+    O: Serialize,
+    E: Into<Box<dyn Error>>,
+    F: FnOnce(I) -> Result<O, E>,
+{
+    let input = read_input(format, reader)?;
+    let output = function(input).map_err(|error| RunError::Function(error.into()))?;
+    write_output(format, writer, &output)
+}
+
+/// Runs `function` like [`run_with`], checking its result against `schema`
+/// before it is written in debug builds. A result that doesn't match is an
+/// [`RunError::Output`] and nothing is written.
+#[cfg(feature = "validate")]
+pub fn run_validated_with<I, O, E, F>(
+    schema: &str,
+    format: Format,
+    reader: impl Read,
+    writer: impl Write,
+    function: F,
+) -> Result<(), RunError>
+where
+    I: DeserializeOwned,
+    O: Serialize,
+    E: Into<Box<dyn Error>>,
+    F: FnOnce(I) -> Result<O, E>,
+{
+    let input = read_input(format, reader)?;
+    let output = function(input).map_err(|error| RunError::Function(error.into()))?;
+    if cfg!(debug_assertions) {
+        crate::validate::validate(schema, &output)
+            .map_err(|error| RunError::Output(error.into()))?;
+    }
+    write_output(format, writer, &output)
+}
+
+fn read_input<I: DeserializeOwned>(format: Format, mut reader: impl Read) -> Result<I, RunError> {
+    let mut input = Vec::new();
+    reader
+        .read_to_end(&mut input)
+        .map_err(|error| RunError::Input(error.into()))?;
+    format.decode(&input).map_err(RunError::Input)
+}
+
+fn write_output<O: Serialize>(
+    format: Format,
+    writer: impl Write,
+    output: &O,
+) -> Result<(), RunError> {
+    let mut writer = BufWriter::new(writer);
+    format
+        .encode(&mut writer, output)
+        .map_err(RunError::Output)?;
+    writer
+        .flush()
+        .map_err(|error| RunError::Output(error.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+    struct Message {
+        value: i32,
+    }
+
+    fn double(input: Message) -> Result<Message, Box<dyn Error>> {
+        if input.value < 0 {
+            return Err("negative value".into());
+        }
+        Ok(Message {
+            value: input.value * 2,
+        })
+    }
+
+    fn run_json(input: &str) -> Result<String, RunError> {
+        let mut output = Vec::new();
+        run_with(Format::Json, input.as_bytes(), &mut output, double)?;
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_json() {
+        assert_eq!(run_json(r#"{"value":21}"#).unwrap(), r#"{"value":42}"#);
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = run_json(r#"{"value":"21"}"#).unwrap_err();
+        assert!(matches!(error, RunError::Input(_)));
+        assert!(error.to_string().starts_with("unable to read the input: "));
+
+        let error = run_json(r#"{"value":-1}"#).unwrap_err();
+        assert!(matches!(error, RunError::Function(_)));
+        assert_eq!(error.to_string(), "negative value");
+    }
+
+    #[cfg(all(feature = "validate", debug_assertions))]
+    #[test]
+    fn test_validated() {
+        let schema = "input FunctionResult { value: Int! }";
+        let run = |function: fn(Message) -> Result<serde_json::Value, Box<dyn Error>>| {
+            let mut output = Vec::new();
+            run_validated_with(
+                schema,
+                Format::Json,
+                &b"{\"value\":21}"[..],
+                &mut output,
+                function,
+            )
+            .map(|()| String::from_utf8(output).unwrap())
+        };
+
+        assert_eq!(
+            run(|input| Ok(serde_json::json!({ "value": input.value }))).unwrap(),
+            r#"{"value":21}"#
+        );
+        let error = run(|_| Ok(serde_json::json!({}))).unwrap_err();
+        assert!(matches!(error, RunError::Output(_)));
+        assert_eq!(
+            error.to_string(),
+            "unable to write the output: the result doesn't match the schema\n  FunctionResult.value: is required"
+        );
+    }
+
+    #[cfg(feature = "messagepack")]
+    #[test]
+    fn test_messagepack() {
+        let input = rmp_serde::to_vec_named(&Message { value: 21 }).unwrap();
+        let mut output = Vec::new();
+        run_with(Format::MessagePack, input.as_slice(), &mut output, double).unwrap();
+
+        assert_eq!(
+            output,
+            rmp_serde::to_vec_named(&Message { value: 42 }).unwrap()
+        );
+    }
+}
@@ -0,0 +1,10 @@
+pub type Boolean = bool;
+pub type Float = f64;
+pub type Int = i32;
+pub type ID = String;
+
+/// Exact decimal, serialized as a string (e.g. `"29.99"`).
+pub use rust_decimal::Decimal;
+
+pub use crate::messages::LanguageCode;
+pub use crate::money::CurrencyCode;
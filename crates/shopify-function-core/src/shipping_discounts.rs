@@ -0,0 +1,3 @@
+//! Output types for the `shipping_discounts` API.
+
+include!(concat!(env!("OUT_DIR"), "/shipping_discounts.rs"));
@@ -0,0 +1,170 @@
+//! `proptest` strategies for the parts of function inputs every function
+//! reads, to build property tests that generate the input as JSON and
+//! deserialize it like the function does.
+//!
+//! ```ignore
+//! let input = (strategies::cart_lines(), strategies::metafield(configuration()))
+//!     .prop_map(|(lines, metafield)| json!({
+//!         "cart": { "lines": lines },
+//!         "discountNode": { "metafield": metafield },
+//!     }));
+//! ```
+
+use proptest::prelude::*;
+use serde::Serialize;
+use serde_json::Value;
+
+/// A global ID of type `kind` among `count`, few enough that they repeat.
+pub fn gid(kind: &'static str, count: u32) -> impl Strategy<Value = String> {
+    (0..count).prop_map(move |n| format!("gid://shopify/{}/{}", kind, n))
+}
+
+/// A positive decimal amount serialized as a string, e.g. `"12.34"`.
+pub fn amount() -> impl Strategy<Value = String> {
+    (0..100_000u32).prop_map(|cents| format!("{}.{:02}", cents / 100, cents % 100))
+}
+
+/// The quantity of a cart line, sometimes 0 or negative: the platform doesn't
+/// send those, but nothing in the input type rules them out.
+pub fn quantity() -> impl Strategy<Value = i32> {
+    prop_oneof![
+        1 => -2..=0i32,
+        4 => 1..20i32,
+    ]
+}
+
+/// A cart line of one of 5 variants, variant `n` belonging to product `n % 3`,
+/// or sometimes of merchandise that isn't a variant.
+pub fn cart_line() -> impl Strategy<Value = Value> {
+    (
+        gid("CartLine", 100),
+        quantity(),
+        prop::option::weighted(0.9, 0..5u32),
+    )
+        .prop_map(|(id, quantity, variant)| {
+            let merchandise = variant.map_or(serde_json::json!({}), |variant| {
+                serde_json::json!({
+                    "id": format!("gid://shopify/ProductVariant/{}", variant),
+                    "product": { "id": format!("gid://shopify/Product/{}", variant % 3) },
+                })
+            });
+            serde_json::json!({ "id": id, "quantity": quantity, "merchandise": merchandise })
+        })
+}
+
+/// The lines of a cart, where lines often share a variant.
+pub fn cart_lines() -> impl Strategy<Value = Vec<Value>> {
+    prop::collection::vec(cart_line(), 0..8)
+}
+
+/// Any JSON value, nested a few levels deep.
+pub fn json() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::from),
+        any::<i64>().prop_map(Value::from),
+        any::<f64>().prop_map(Value::from),
+        ".{0,10}".prop_map(Value::from),
+    ];
+    leaf.prop_recursive(3, 16, 4, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..4).prop_map(Value::from),
+            prop::collection::btree_map("[a-zA-Z]{1,12}", inner, 0..4)
+                .prop_map(|map| Value::Object(map.into_iter().collect())),
+        ]
+    })
+}
+
+/// The languages of the stores messages are tested in.
+const LANGUAGE_CODES: [&str; 4] = ["EN", "FR", "DE", "PT_BR"];
+
+/// A buyer's `LanguageCode`, e.g. `"PT_BR"`.
+pub fn language_code() -> impl Strategy<Value = &'static str> {
+    prop::sample::select(&LANGUAGE_CODES[..])
+}
+
+/// `Messages` with templates using the `placeholders`, and sometimes unknown
+/// ones, and a fallback language that may have no template.
+pub fn messages(placeholders: &'static [&'static str]) -> impl Strategy<Value = Value> {
+    let template = prop::collection::vec(
+        prop_oneof![
+            "[a-zA-Z %$]{0,10}",
+            prop::sample::select(placeholders).prop_map(|name| format!("{{{}}}", name)),
+            Just("{unknown}".to_string()),
+        ],
+        0..4,
+    )
+    .prop_map(|parts| parts.concat());
+    (
+        prop::collection::btree_map(language_code(), template, 0..3),
+        language_code(),
+    )
+        .prop_map(|(templates, fallback_language)| {
+            serde_json::json!({ "templates": templates, "fallbackLanguage": fallback_language })
+        })
+}
+
+/// The `metafield` holding the configuration: missing, a valid
+/// `configuration`, or malformed, truncated or mistyped JSON.
+pub fn metafield<C>(
+    configuration: impl Strategy<Value = C> + 'static,
+) -> impl Strategy<Value = Value>
+where
+    C: Serialize + std::fmt::Debug + 'static,
+{
+    let valid = configuration
+        .prop_map(|configuration| {
+            serde_json::to_string(&configuration).expect("the configuration serializes")
+        })
+        .boxed();
+    let value = prop_oneof![
+        4 => valid.clone(),
+        1 => (valid, any::<prop::sample::Index>())
+            .prop_map(|(value, index)| {
+                let length = index.index(value.chars().count());
+                value.chars().take(length).collect::<String>()
+            }),
+        1 => json().prop_map(|value| value.to_string()),
+        1 => ".{0,20}",
+    ];
+    prop_oneof![
+        1 => Just(Value::Null),
+        4 => value.prop_map(|value| serde_json::json!({ "value": value })),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn test_metafield(metafield in metafield(0..10u8)) {
+            prop_assert!(metafield.is_null() || metafield["value"].is_string());
+        }
+
+        #[test]
+        fn test_gid(id in gid("ProductVariant", 3)) {
+            let n = id.strip_prefix("gid://shopify/ProductVariant/");
+            prop_assert!(matches!(n, Some("0" | "1" | "2")), "{}", id);
+        }
+
+        #[test]
+        fn test_messages(messages in messages(&["percent"])) {
+            prop_assert!(serde_json::from_value::<crate::messages::Messages>(messages).is_ok());
+        }
+
+        #[test]
+        fn test_cart_line(line in cart_line()) {
+            prop_assert!((-2..20).contains(&line["quantity"].as_i64().unwrap()));
+            let id = line["merchandise"]["id"].as_str().unwrap_or("gid://shopify/ProductVariant/0");
+            let n: u32 = id.strip_prefix("gid://shopify/ProductVariant/").unwrap().parse().unwrap();
+            prop_assert!(n < 5);
+        }
+
+        #[test]
+        fn test_amount(amount in amount()) {
+            prop_assert!(amount.parse::<rust_decimal::Decimal>().is_ok());
+        }
+    }
+}
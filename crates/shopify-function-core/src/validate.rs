@@ -0,0 +1,588 @@
+//! Checks a function result against the input type of the result mutation in
+//! its `schema.graphql`, so results the API would reject fail in tests and
+//! debug builds instead of at checkout.
+//!
+//! Besides the types, required fields, enum values and `@oneOf` exclusivity,
+//! the constraints documented on fields are checked: `The value is validated
+//! against: >= 0.`, `= "PRODUCT_VARIANT"`, `The amount must be greater than or
+//! equal to 0.` and `targets should contain only one type of `Target``.
+//!
+//! ```ignore
+//! let result = function(input)?;
+//! validate::validate(include_str!("../schema.graphql"), &result)?;
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use graphql_parser::schema::{self, Definition, InputValue, Type, TypeDefinition};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use serde_json::Value;
+
+/// A place where a result doesn't match the schema.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    /// Where the value is, e.g. `FunctionResult.discounts[0].value`.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+#[derive(Debug)]
+pub enum ValidationError {
+    /// The schema can't be parsed or has no result type.
+    Schema(String),
+    /// The result doesn't match the schema.
+    Invalid(Vec<Violation>),
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValidationError::Schema(message) => write!(f, "invalid schema: {}", message),
+            ValidationError::Invalid(violations) => {
+                write!(f, "the result doesn't match the schema")?;
+                for violation in violations {
+                    write!(f, "\n  {}", violation)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Validates the serialized `result` against `schema`.
+pub fn validate<O: Serialize>(schema: &str, result: &O) -> Result<(), ValidationError> {
+    let value = serde_json::to_value(result)
+        .map_err(|error| ValidationError::Schema(format!("unable to serialize: {}", error)))?;
+    Validator::new(schema)?.validate(&value)
+}
+
+/// Panics if the serialized `result` doesn't match `schema`, for tests to fail
+/// on results the API would reject.
+///
+/// ```ignore
+/// let result = function(input)?;
+/// validate::assert_valid(include_str!("../schema.graphql"), &result);
+/// ```
+pub fn assert_valid<O: Serialize>(schema: &str, result: &O) {
+    if let Err(error) = validate(schema, result) {
+        panic!("{}", error);
+    }
+}
+
+/// Calls `function` on `input`, panicking if its result doesn't match `schema`,
+/// for the tests of a function to check every result it returns.
+///
+/// ```ignore
+/// fn function(input: input::Input) -> Result<FunctionResult, Box<dyn std::error::Error>> {
+///     validate::checked(include_str!("../schema.graphql"), super::function, input)
+/// }
+/// ```
+pub fn checked<I, O, E, F>(schema: &str, function: F, input: I) -> Result<O, E>
+where
+    O: Serialize,
+    F: FnOnce(I) -> Result<O, E>,
+{
+    function(input).inspect(|result| assert_valid(schema, result))
+}
+
+/// The input types of a parsed `schema.graphql`.
+pub struct Validator<'a> {
+    types: HashMap<&'a str, TypeDefinition<'a, &'a str>>,
+    result_type: &'a str,
+}
+
+impl<'a> Validator<'a> {
+    pub fn new(schema: &'a str) -> Result<Self, ValidationError> {
+        let document = schema::parse_schema::<&str>(schema)
+            .map_err(|error| ValidationError::Schema(error.to_string()))?;
+
+        let mut types = HashMap::new();
+        let mut mutation_type = None;
+        for definition in document.definitions {
+            match definition {
+                Definition::SchemaDefinition(definition) => mutation_type = definition.mutation,
+                Definition::TypeDefinition(definition) => {
+                    if let Some(name) = type_definition_name(&definition) {
+                        types.insert(name, definition);
+                    }
+                }
+                Definition::TypeExtension(_) | Definition::DirectiveDefinition(_) => {}
+            }
+        }
+
+        let result_type = match mutation_type.map(|name| types.get(name)) {
+            None => "FunctionResult",
+            Some(Some(TypeDefinition::Object(mutation))) => mutation
+                .fields
+                .iter()
+                .flat_map(|field| field.arguments.iter())
+                .map(|argument| named_type(&argument.value_type))
+                .next()
+                .ok_or_else(|| {
+                    ValidationError::Schema(format!("`{}` has no arguments", mutation.name))
+                })?,
+            Some(_) => {
+                return Err(ValidationError::Schema(
+                    "the mutation type isn't defined".to_string(),
+                ))
+            }
+        };
+        Ok(Validator { types, result_type })
+    }
+
+    /// Validates a result serialized as JSON.
+    pub fn validate(&self, result: &Value) -> Result<(), ValidationError> {
+        let mut violations = Vec::new();
+        let ty = Type::NonNullType(Box::new(Type::NamedType(self.result_type)));
+        self.check(&mut violations, self.result_type, &ty, result);
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError::Invalid(violations))
+        }
+    }
+
+    fn check(
+        &self,
+        violations: &mut Vec<Violation>,
+        path: &str,
+        ty: &Type<'a, &'a str>,
+        value: &Value,
+    ) {
+        match (ty, value) {
+            (Type::NonNullType(_), Value::Null) => violations.push(Violation {
+                path: path.to_string(),
+                message: "is required".to_string(),
+            }),
+            (Type::NonNullType(ty), value) => self.check(violations, path, ty, value),
+            (_, Value::Null) => {}
+            (Type::ListType(ty), Value::Array(items)) => {
+                for (index, item) in items.iter().enumerate() {
+                    self.check(violations, &format!("{}[{}]", path, index), ty, item);
+                }
+            }
+            (Type::ListType(_), value) => violations.push(Violation {
+                path: path.to_string(),
+                message: format!("expected a list, got {}", value),
+            }),
+            (Type::NamedType(name), value) => self.check_named(violations, path, name, value),
+        }
+    }
+
+    fn check_named(&self, violations: &mut Vec<Violation>, path: &str, name: &str, value: &Value) {
+        let valid = match (name, self.types.get(name)) {
+            ("Boolean", _) => value.is_boolean(),
+            ("Int", _) => value.as_i64().is_some_and(|int| i32::try_from(int).is_ok()),
+            ("Float", _) => value.is_number(),
+            ("String", _) => value.is_string(),
+            ("ID", _) => value.is_string() || value.is_i64(),
+            ("Decimal", _) => decimal(value).is_some(),
+            (_, Some(TypeDefinition::Enum(definition))) => value
+                .as_str()
+                .is_some_and(|value| definition.values.iter().any(|v| v.name == value)),
+            (_, Some(TypeDefinition::InputObject(definition))) if value.is_object() => {
+                let one_of = definition.directives.iter().any(|d| d.name == "oneOf");
+                return self.check_object(violations, path, &definition.fields, one_of, value);
+            }
+            (_, Some(TypeDefinition::InputObject(_))) => false,
+            // Other scalars, e.g. `Void`, aren't checked.
+            _ => true,
+        };
+        if !valid {
+            violations.push(Violation {
+                path: path.to_string(),
+                message: format!("expected {}, got {}", name, value),
+            });
+        }
+    }
+
+    fn check_object(
+        &self,
+        violations: &mut Vec<Violation>,
+        path: &str,
+        fields: &[InputValue<'a, &'a str>],
+        one_of: bool,
+        value: &Value,
+    ) {
+        let object = value.as_object().expect("checked by the caller");
+        for key in object.keys() {
+            if !fields.iter().any(|field| field.name == key) {
+                violations.push(Violation {
+                    path: path.to_string(),
+                    message: format!("unknown field `{}`", key),
+                });
+            }
+        }
+
+        if one_of {
+            let set: Vec<&str> = fields
+                .iter()
+                .filter(|field| !object.get(field.name).unwrap_or(&Value::Null).is_null())
+                .map(|field| field.name)
+                .collect();
+            if set.len() != 1 {
+                violations.push(Violation {
+                    path: path.to_string(),
+                    message: format!(
+                        "exactly one field must be set, got {}",
+                        if set.is_empty() {
+                            "none".to_string()
+                        } else {
+                            set.join(", ")
+                        }
+                    ),
+                });
+            }
+        }
+
+        for field in fields {
+            let path = format!("{}.{}", path, field.name);
+            let value = object.get(field.name).unwrap_or(&Value::Null);
+            // A field with a default value can be omitted, even if it's non-null.
+            let ty = match (&field.value_type, &field.default_value) {
+                (Type::NonNullType(ty), Some(_)) => ty.as_ref(),
+                (ty, _) => ty,
+            };
+            // `@oneOf` members are nullable, exclusivity is checked above.
+            self.check(violations, &path, ty, value);
+            if let Some(constraint) = field.description.as_deref().and_then(constraint) {
+                if let Err(message) = constraint.check(value) {
+                    violations.push(Violation { path, message });
+                }
+            }
+        }
+    }
+}
+
+/// A constraint documented on a field.
+#[derive(Debug, PartialEq)]
+enum Constraint {
+    Compare(&'static str, Decimal),
+    Equals(String),
+    /// All the `@oneOf` items of the list set the same field.
+    SameType,
+}
+
+impl Constraint {
+    fn check(&self, value: &Value) -> Result<(), String> {
+        match (self, value) {
+            (_, Value::Null) => Ok(()),
+            (Constraint::Compare(operator, bound), value) => {
+                let number = match decimal(value) {
+                    Some(number) => number,
+                    // The type mismatch is already reported.
+                    None => return Ok(()),
+                };
+                let valid = match *operator {
+                    ">=" => number >= *bound,
+                    ">" => number > *bound,
+                    "<=" => number <= *bound,
+                    _ => number < *bound,
+                };
+                if valid {
+                    Ok(())
+                } else {
+                    Err(format!("must be {} {}, got {}", operator, bound, number))
+                }
+            }
+            (Constraint::Equals(expected), value) => match value.as_str() {
+                Some(value) if value != expected => {
+                    Err(format!("must be `{}`, got `{}`", expected, value))
+                }
+                _ => Ok(()),
+            },
+            (Constraint::SameType, Value::Array(items)) => {
+                let mut types: Vec<&str> = items
+                    .iter()
+                    .filter_map(Value::as_object)
+                    .flat_map(|item| item.iter())
+                    .filter(|(_, value)| !value.is_null())
+                    .map(|(key, _)| key.as_str())
+                    .collect();
+                types.sort_unstable();
+                types.dedup();
+                if types.len() > 1 {
+                    Err(format!(
+                        "must contain only one type, got {}",
+                        types.join(", ")
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+            (Constraint::SameType, _) => Ok(()),
+        }
+    }
+}
+
+/// The constraint documented in a field description, if it's one we understand.
+fn constraint(description: &str) -> Option<Constraint> {
+    for line in description.lines().map(str::trim) {
+        if let Some(rule) = line.strip_prefix("The value is validated against:") {
+            let rule = rule.trim().trim_end_matches('.');
+            if rule.contains("should contain only one type of") {
+                return Some(Constraint::SameType);
+            }
+            if let Some(expected) = rule.strip_prefix('=') {
+                return Some(Constraint::Equals(
+                    expected.trim().trim_matches('"').to_string(),
+                ));
+            }
+            return comparison(rule);
+        }
+        if let Some((_, rule)) = line.split_once("must be ") {
+            let rule = rule.trim_end_matches('.');
+            let operators = [
+                ("greater than or equal to", ">="),
+                ("greater than", ">"),
+                ("less than or equal to", "<="),
+                ("less than", "<"),
+            ];
+            for (words, operator) in operators {
+                if let Some(bound) = rule.strip_prefix(words) {
+                    return comparison(&format!("{} {}", operator, bound.trim()));
+                }
+            }
+        }
+    }
+    None
+}
+
+fn comparison(rule: &str) -> Option<Constraint> {
+    let (operator, bound) = [">=", "<=", ">", "<"]
+        .iter()
+        .find_map(|operator| Some((*operator, rule.strip_prefix(operator)?)))?;
+    Some(Constraint::Compare(
+        operator,
+        Decimal::from_str(bound.trim()).ok()?,
+    ))
+}
+
+/// A number, or a decimal serialized as a string.
+fn decimal(value: &Value) -> Option<Decimal> {
+    match value {
+        Value::String(value) => Decimal::from_str(value).ok(),
+        Value::Number(number) => {
+            let number = number.to_string();
+            Decimal::from_str(&number)
+                .or_else(|_| Decimal::from_scientific(&number))
+                .ok()
+        }
+        _ => None,
+    }
+}
+
+fn type_definition_name<'a>(definition: &TypeDefinition<'a, &'a str>) -> Option<&'a str> {
+    match definition {
+        TypeDefinition::Object(t) => Some(t.name),
+        TypeDefinition::Enum(t) => Some(t.name),
+        TypeDefinition::InputObject(t) => Some(t.name),
+        TypeDefinition::Scalar(_) | TypeDefinition::Interface(_) | TypeDefinition::Union(_) => None,
+    }
+}
+
+fn named_type<'a>(ty: &Type<'a, &'a str>) -> &'a str {
+    match ty {
+        Type::NamedType(name) => name,
+        Type::ListType(inner) | Type::NonNullType(inner) => named_type(inner),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::product_discounts;
+    use serde_json::json;
+
+    const SCHEMA: &str = include_str!("../schemas/2022-07/product_discounts.graphql");
+    const ORDER_DISCOUNTS_SCHEMA: &str = include_str!("../schemas/2022-07/order_discounts.graphql");
+
+    fn violations(schema: &str, result: Value) -> Vec<String> {
+        match Validator::new(schema).unwrap().validate(&result) {
+            Ok(()) => vec![],
+            Err(ValidationError::Invalid(violations)) => {
+                violations.iter().map(ToString::to_string).collect()
+            }
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    fn discount(value: Value) -> Value {
+        json!({
+            "discountApplicationStrategy": "FIRST",
+            "discounts": [{
+                "targets": [{ "productVariant": { "id": "gid://shopify/ProductVariant/1" } }],
+                "value": value,
+            }],
+        })
+    }
+
+    #[test]
+    fn test_valid_result() {
+        use product_discounts::*;
+
+        let result = FunctionResult {
+            discount_application_strategy: DiscountApplicationStrategy::First,
+            discounts: vec![Discount {
+                conditions: None,
+                message: Some("10% off".to_string()),
+                targets: vec![Target::ProductVariant {
+                    id: "gid://shopify/ProductVariant/1".to_string(),
+                    quantity: Some(2),
+                }],
+                value: product_discounts::Value::Percentage(Percentage { value: 10.0 }),
+            }],
+        };
+
+        validate(SCHEMA, &result).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "FunctionResult.discounts[0].value.percentage.value: must be >= 0")]
+    fn test_assert_valid() {
+        assert_valid(
+            SCHEMA,
+            &discount(json!({ "percentage": { "value": -1.0 } })),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "FunctionResult.discounts[0].value.percentage.value: must be >= 0")]
+    fn test_checked() {
+        let function =
+            |value: f64| Ok::<_, String>(discount(json!({ "percentage": { "value": value } })));
+
+        assert!(checked(SCHEMA, function, 10.0).is_ok());
+        assert_eq!(
+            checked(SCHEMA, |_: ()| Err::<Value, _>("error"), ()),
+            Err("error")
+        );
+        let _ = checked(SCHEMA, function, -1.0);
+    }
+
+    #[test]
+    fn test_types_and_required_fields() {
+        assert_eq!(
+            violations(
+                SCHEMA,
+                json!({ "discountApplicationStrategy": "ALL", "discounts": {}, "extra": 1 })
+            ),
+            vec![
+                "FunctionResult: unknown field `extra`",
+                "FunctionResult.discountApplicationStrategy: expected DiscountApplicationStrategy, got \"ALL\"",
+                "FunctionResult.discounts: expected a list, got {}",
+            ]
+        );
+        assert_eq!(
+            violations(
+                SCHEMA,
+                json!({ "discounts": [{ "targets": [], "value": { "percentage": { "value": "10" } } }] })
+            ),
+            vec![
+                "FunctionResult.discountApplicationStrategy: is required",
+                "FunctionResult.discounts[0].value.percentage.value: expected Float, got \"10\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_one_of() {
+        assert_eq!(
+            violations(SCHEMA, discount(json!({}))),
+            vec!["FunctionResult.discounts[0].value: exactly one field must be set, got none"]
+        );
+        assert_eq!(
+            violations(
+                SCHEMA,
+                discount(json!({
+                    "percentage": { "value": 10.0 },
+                    "fixedAmount": { "amount": "5.0" },
+                }))
+            ),
+            vec!["FunctionResult.discounts[0].value: exactly one field must be set, got fixedAmount, percentage"]
+        );
+    }
+
+    #[test]
+    fn test_documented_constraints() {
+        assert_eq!(
+            violations(
+                SCHEMA,
+                discount(json!({ "fixedAmount": { "amount": "-5.0" } }))
+            ),
+            vec!["FunctionResult.discounts[0].value.fixedAmount.amount: must be >= 0, got -5.0"]
+        );
+        assert_eq!(
+            violations(SCHEMA, discount(json!({ "percentage": { "value": -1 } }))),
+            vec!["FunctionResult.discounts[0].value.percentage.value: must be >= 0, got -1"]
+        );
+
+        let mut result = discount(json!({ "percentage": { "value": 10 } }));
+        result["discounts"][0]["targets"][0]["productVariant"]["quantity"] = json!(0);
+        result["discounts"][0]["conditions"] = json!([{
+            "productMinimumQuantity": { "ids": [], "minimumQuantity": 2, "targetType": "ORDER_SUBTOTAL" }
+        }]);
+        assert_eq!(
+            violations(SCHEMA, result),
+            vec![
+                "FunctionResult.discounts[0].conditions[0].productMinimumQuantity.targetType: must be `PRODUCT_VARIANT`, got `ORDER_SUBTOTAL`",
+                "FunctionResult.discounts[0].targets[0].productVariant.quantity: must be > 0, got 0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_targets_of_one_type() {
+        let result = json!({
+            "discountApplicationStrategy": "FIRST",
+            "discounts": [{
+                "targets": [
+                    { "orderSubtotal": { "excludedVariantIds": [] } },
+                    { "productVariant": { "id": "gid://shopify/ProductVariant/1" } },
+                ],
+                "value": { "percentage": { "value": 10 } },
+            }],
+        });
+
+        assert_eq!(
+            violations(ORDER_DISCOUNTS_SCHEMA, result),
+            vec!["FunctionResult.discounts[0].targets: must contain only one type, got orderSubtotal, productVariant"]
+        );
+    }
+
+    #[test]
+    fn test_constraints() {
+        assert_eq!(
+            constraint("The percentage value.\n\nThe value is validated against: >= 0."),
+            Some(Constraint::Compare(">=", Decimal::ZERO))
+        );
+        assert_eq!(
+            constraint("The amount must be greater than or equal to 0."),
+            Some(Constraint::Compare(">=", Decimal::ZERO))
+        );
+        assert_eq!(
+            constraint("   The value is validated against: = \"ORDER_SUBTOTAL\"."),
+            Some(Constraint::Equals("ORDER_SUBTOTAL".to_string()))
+        );
+        assert_eq!(constraint("The discount message."), None);
+    }
+
+    #[test]
+    fn test_schema_errors() {
+        assert!(matches!(
+            Validator::new("input {"),
+            Err(ValidationError::Schema(_))
+        ));
+    }
+}
@@ -0,0 +1,291 @@
+//! Runs a function compiled to `wasm32-wasi` the way the platform does: the
+//! input on stdin, the result on stdout, logs on stderr, with instructions
+//! counted as fuel and the function stopped once it runs out.
+//!
+//! ```no_run
+//! let function = std::fs::read("target/wasm32-wasi/release/product-discount.wasm")?;
+//! let input = br#"{"cart":{"lines":[]}}"#;
+//! let run = shopify_function_runner::run(&function, input, "_start", &Default::default())?;
+//! println!("{}", run);
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use std::fmt;
+
+use anyhow::Context;
+use wasi_common::pipe::{ReadPipe, WritePipe};
+use wasi_common::sync::WasiCtxBuilder;
+use wasi_common::WasiCtx;
+use wasmtime::{Config, Engine, Linker, Module, Store, Trap};
+
+/// The resource limits the platform enforces on a function run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    pub input_size: usize,
+    pub output_size: usize,
+    pub logs_size: usize,
+    pub memory: usize,
+    pub instructions: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            input_size: 64 * 1024,
+            output_size: 20 * 1024,
+            logs_size: 1024,
+            memory: 10 * 1024 * 1024,
+            instructions: 11_000_000,
+        }
+    }
+}
+
+/// The result of running a function and the resources it used.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FunctionRun {
+    pub input_size: usize,
+    pub output: Vec<u8>,
+    pub logs: String,
+    /// The size of the linear memory when the function returned, which only grows.
+    pub memory: usize,
+    /// The fuel consumed, about one unit per instruction.
+    pub instructions: u64,
+    /// Why the function failed, if it trapped or exited with an error.
+    pub error: Option<String>,
+}
+
+impl FunctionRun {
+    /// The limits this run went over.
+    pub fn exceeded(&self, limits: &Limits) -> Vec<&'static str> {
+        let mut exceeded = Vec::new();
+        if self.input_size > limits.input_size {
+            exceeded.push("input size");
+        }
+        if self.output.len() > limits.output_size {
+            exceeded.push("output size");
+        }
+        if self.logs.len() > limits.logs_size {
+            exceeded.push("logs size");
+        }
+        if self.memory > limits.memory {
+            exceeded.push("memory");
+        }
+        if self.instructions > limits.instructions {
+            exceeded.push("instructions");
+        }
+        exceeded
+    }
+
+    /// A report of the resources used against `limits`.
+    pub fn report(&self, limits: &Limits) -> String {
+        let rows = [
+            ("Input", bytes(self.input_size), bytes(limits.input_size)),
+            (
+                "Output",
+                bytes(self.output.len()),
+                bytes(limits.output_size),
+            ),
+            ("Logs", bytes(self.logs.len()), bytes(limits.logs_size)),
+            ("Memory", bytes(self.memory), bytes(limits.memory)),
+            (
+                "Instructions",
+                self.instructions.to_string(),
+                limits.instructions.to_string(),
+            ),
+        ];
+        rows.iter()
+            .map(|(name, used, limit)| format!("{:>12}: {} (limit {})\n", name, used, limit))
+            .collect()
+    }
+}
+
+impl fmt::Display for FunctionRun {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.report(&Limits::default()))?;
+        for name in self.exceeded(&Limits::default()) {
+            writeln!(f, "Exceeded the {} limit", name)?;
+        }
+        Ok(())
+    }
+}
+
+fn bytes(size: usize) -> String {
+    match size {
+        size if size >= 1024 * 1024 => format!("{:.1} MB", size as f64 / (1024.0 * 1024.0)),
+        size if size >= 1024 => format!("{:.1} KB", size as f64 / 1024.0),
+        size => format!("{} B", size),
+    }
+}
+
+/// Runs the `export` of `function`, a wasm binary or WAT module, on `input`,
+/// stopping it one instruction past `limits.instructions` so that a runaway
+/// function is reported as exceeding the limit instead of hanging.
+pub fn run(
+    function: &[u8],
+    input: &[u8],
+    export: &str,
+    limits: &Limits,
+) -> anyhow::Result<FunctionRun> {
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config)?;
+    let module = Module::new(&engine, function).context("unable to load the function")?;
+
+    let stdout = WritePipe::new_in_memory();
+    let stderr = WritePipe::new_in_memory();
+    let wasi = WasiCtxBuilder::new()
+        .stdin(Box::new(ReadPipe::from(input)))
+        .stdout(Box::new(stdout.clone()))
+        .stderr(Box::new(stderr.clone()))
+        .build();
+    let mut linker: Linker<WasiCtx> = Linker::new(&engine);
+    wasi_common::sync::add_to_linker(&mut linker, |wasi| wasi)?;
+
+    let mut store = Store::new(&engine, wasi);
+    let fuel = limits.instructions.saturating_add(1);
+    store.set_fuel(fuel)?;
+    let instance = linker.instantiate(&mut store, &module)?;
+    let start = instance
+        .get_typed_func::<(), ()>(&mut store, export)
+        .with_context(|| format!("the function doesn't export `{}`", export))?;
+
+    let error = match start.call(&mut store, ()) {
+        Ok(()) => None,
+        Err(error) => match error.downcast_ref::<wasi_common::I32Exit>() {
+            Some(exit) if exit.0 == 0 => None,
+            Some(exit) => Some(format!("exited with code {}", exit.0)),
+            None if error.downcast_ref::<Trap>() == Some(&Trap::OutOfFuel) => {
+                Some("exceeded the instructions limit".to_string())
+            }
+            None => Some(format!("{:?}", error)),
+        },
+    };
+    let instructions = fuel - store.get_fuel()?;
+    let memory = instance
+        .get_memory(&mut store, "memory")
+        .map(|memory| memory.data_size(&store))
+        .unwrap_or_default();
+    drop(store);
+
+    Ok(FunctionRun {
+        input_size: input.len(),
+        output: into_bytes(stdout)?,
+        logs: String::from_utf8_lossy(&into_bytes(stderr)?).into_owned(),
+        memory,
+        instructions,
+        error,
+    })
+}
+
+fn into_bytes(pipe: WritePipe<std::io::Cursor<Vec<u8>>>) -> anyhow::Result<Vec<u8>> {
+    let cursor = pipe
+        .try_into_inner()
+        .map_err(|_| anyhow::anyhow!("the function's output is still in use"))?;
+    Ok(cursor.into_inner())
+}
+
+/// Encodes a JSON input as MessagePack, for checkout scripts.
+pub fn json_to_messagepack(input: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let value: serde_json::Value = serde_json::from_slice(input).context("invalid JSON input")?;
+    Ok(rmp_serde::to_vec_named(&value)?)
+}
+
+/// Decodes a MessagePack output as JSON.
+pub fn messagepack_to_json(output: &[u8]) -> anyhow::Result<serde_json::Value> {
+    rmp_serde::from_slice(output).context("invalid MessagePack output")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Echoes stdin to stdout and logs `hi` to stderr.
+    const ECHO: &str = r#"
+        (module
+          (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 2000) "hi")
+          (func (export "_start")
+            (i32.store (i32.const 0) (i32.const 64))
+            (i32.store (i32.const 4) (i32.const 1024))
+            (drop (call $fd_read (i32.const 0) (i32.const 0) (i32.const 1) (i32.const 8)))
+            (i32.store (i32.const 4) (i32.load (i32.const 8)))
+            (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))
+            (i32.store (i32.const 16) (i32.const 2000))
+            (i32.store (i32.const 20) (i32.const 2))
+            (drop (call $fd_write (i32.const 2) (i32.const 16) (i32.const 1) (i32.const 24)))))
+    "#;
+
+    #[test]
+    fn test_run() {
+        let run = run(
+            ECHO.as_bytes(),
+            br#"{"value":1}"#,
+            "_start",
+            &Limits::default(),
+        )
+        .unwrap();
+
+        assert_eq!(run.output, br#"{"value":1}"#);
+        assert_eq!(run.logs, "hi");
+        assert_eq!(run.input_size, 11);
+        assert_eq!(run.memory, 64 * 1024);
+        assert!(run.instructions > 0);
+        assert_eq!(run.error, None);
+        assert!(run.exceeded(&Limits::default()).is_empty());
+    }
+
+    #[test]
+    fn test_limits() {
+        let run = run(ECHO.as_bytes(), b"{}", "_start", &Limits::default()).unwrap();
+        let limits = Limits {
+            logs_size: 1,
+            instructions: 1,
+            ..Limits::default()
+        };
+
+        assert_eq!(run.exceeded(&limits), vec!["logs size", "instructions"]);
+        assert!(run
+            .report(&limits)
+            .contains("        Logs: 2 B (limit 1 B)\n"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let trap = r#"(module (func (export "_start") unreachable))"#;
+        let limits = Limits::default();
+        let run = run(trap.as_bytes(), b"", "_start", &limits).unwrap();
+        assert!(run.error.unwrap().contains("unreachable"));
+
+        assert!(super::run(ECHO.as_bytes(), b"", "run", &limits).is_err());
+        assert!(super::run(b"not wasm", b"", "_start", &limits).is_err());
+    }
+
+    #[test]
+    fn test_instructions_limit() {
+        let looping = r#"(module (func (export "_start") (loop br 0)))"#;
+        let limits = Limits {
+            instructions: 1000,
+            ..Limits::default()
+        };
+        let run = run(looping.as_bytes(), b"", "_start", &limits).unwrap();
+
+        assert_eq!(
+            run.error.as_deref(),
+            Some("exceeded the instructions limit")
+        );
+        assert_eq!(run.instructions, 1001);
+        assert_eq!(run.exceeded(&limits), vec!["instructions"]);
+    }
+
+    #[test]
+    fn test_messagepack() {
+        let input = json_to_messagepack(br#"{"value":[1,"a"]}"#).unwrap();
+
+        assert_eq!(
+            messagepack_to_json(&input).unwrap(),
+            serde_json::json!({"value": [1, "a"]})
+        );
+    }
+}
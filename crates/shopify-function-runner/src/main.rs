@@ -0,0 +1,150 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use anyhow::Context;
+use clap::{Parser, ValueEnum};
+use shopify_function_core::fixtures;
+use shopify_function_core::validate::Validator;
+use shopify_function_runner::{json_to_messagepack, messagepack_to_json, run, Limits};
+
+/// Runs a function's `.wasm` against an input file, prints its output and
+/// logs, and reports its resource usage against the platform limits and
+/// whether the output matches the schema. With `--fixtures`, runs every
+/// `*.input.json` of a directory and compares the outputs with the matching
+/// `*.output.json` instead.
+#[derive(Parser)]
+#[command(name = "function-runner")]
+struct Args {
+    /// The compiled function, e.g. `target/wasm32-wasi/release/<name>.wasm`.
+    #[arg(short, long)]
+    function: PathBuf,
+    /// The input, as JSON.
+    #[arg(short, long, required_unless_present = "fixtures")]
+    input: Option<PathBuf>,
+    /// A directory of golden files, e.g. `tests/fixtures`.
+    #[arg(long, conflicts_with = "input")]
+    fixtures: Option<PathBuf>,
+    /// The wire format the function speaks.
+    #[arg(long, value_enum, default_value_t = Format::Json)]
+    format: Format,
+    /// The function's `schema.graphql`, to check the output against.
+    #[arg(short, long)]
+    schema: Option<PathBuf>,
+    /// The function to call.
+    #[arg(short, long, default_value = "_start")]
+    export: String,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Json,
+    /// Checkout scripts built with the `messagepack` feature.
+    Messagepack,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let result = match &args.fixtures {
+        Some(dir) => run_fixtures(&args, dir),
+        None => run_function(&args),
+    };
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(error) => {
+            eprintln!("error: {:#}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Whether the function succeeded within the limits.
+fn run_function(args: &Args) -> anyhow::Result<bool> {
+    let function = read(&args.function)?;
+    let mut input = read(args.input.as_deref().expect("required without --fixtures"))?;
+    if let Format::Messagepack = args.format {
+        input = json_to_messagepack(&input)?;
+    }
+
+    let run = run(&function, &input, &args.export, &Limits::default())?;
+
+    if !run.logs.is_empty() {
+        println!("Logs:\n{}\n", run.logs.trim_end());
+    }
+    let output = match args.format {
+        Format::Json => serde_json::from_slice(&run.output).ok(),
+        Format::Messagepack => messagepack_to_json(&run.output).ok(),
+    };
+    match &output {
+        Some(output) => println!("Output:\n{}\n", serde_json::to_string_pretty(output)?),
+        None => println!("Output:\n{}\n", String::from_utf8_lossy(&run.output)),
+    }
+    if let Some(error) = &run.error {
+        println!("Error:\n{}\n", error);
+    }
+
+    let mut valid = true;
+    if let (Some(path), Some(output), None) = (&args.schema, &output, &run.error) {
+        let schema = std::fs::read_to_string(path)
+            .with_context(|| format!("unable to read {}", path.display()))?;
+        if let Err(error) = Validator::new(&schema)?.validate(output) {
+            println!("Error:\n{}\n", error);
+            valid = false;
+        }
+    }
+    print!("{}", run);
+
+    Ok(valid && run.error.is_none() && run.exceeded(&Limits::default()).is_empty())
+}
+
+/// Whether the function's output matches every fixture in `dir`, within the limits.
+fn run_fixtures(args: &Args, dir: &Path) -> anyhow::Result<bool> {
+    let function = read(&args.function)?;
+    let bless = std::env::var_os("BLESS").is_some_and(|value| value == "1");
+    let failures = fixtures::check_with(dir, bless, |input: serde_json::Value| {
+        let mut input = serde_json::to_vec(&input)?;
+        if let Format::Messagepack = args.format {
+            input = json_to_messagepack(&input)?;
+        }
+        let run = run(&function, &input, &args.export, &Limits::default())?;
+        if let Some(error) = run.error {
+            anyhow::bail!("{}", error);
+        }
+        if let Some(limit) = run.exceeded(&Limits::default()).first() {
+            anyhow::bail!("exceeded the {} limit", limit);
+        }
+        match args.format {
+            Format::Json => Ok(serde_json::from_slice::<serde_json::Value>(&run.output)?),
+            Format::Messagepack => messagepack_to_json(&run.output),
+        }
+    });
+
+    for failure in &failures {
+        println!("{}\n", failure);
+    }
+    // Counted from the directory, the function isn't called on inputs that
+    // aren't JSON and an empty directory is itself a failure.
+    let count = count_fixtures(dir);
+    println!(
+        "{} of {} fixtures passed",
+        count.saturating_sub(failures.len()),
+        count
+    );
+    Ok(failures.is_empty())
+}
+
+/// The number of `*.input.json` fixtures in `dir`.
+fn count_fixtures(dir: &Path) -> usize {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .filter(|entry| entry.file_name().to_string_lossy().ends_with(".input.json"))
+                .count()
+        })
+        .unwrap_or_default()
+}
+
+fn read(path: &Path) -> anyhow::Result<Vec<u8>> {
+    std::fs::read(path).with_context(|| format!("unable to read {}", path.display()))
+}
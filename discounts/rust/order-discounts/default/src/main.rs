@@ -1,37 +1,38 @@
 use serde::{Deserialize, Serialize};
+use shopify_function_core::config;
+use shopify_function_core::order_discounts::*;
 
-mod api;
-use api::*;
+mod input {
+    #![allow(dead_code)]
+    include!(concat!(env!("OUT_DIR"), "/input.rs"));
+}
+
+#[cfg(test)]
+mod properties;
 
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct Configuration {}
 
-impl Configuration {
-    fn from_str(str: &str) -> Self {
-        serde_json::from_str(str).expect("Unable to parse configuration value from metafield")
-    }
-}
+impl config::Validate for Configuration {}
 
 impl input::Input {
-    pub fn configuration(&self) -> Configuration {
-        match &self.discount_node.metafield {
-            Some(input::Metafield { value }) => Configuration::from_str(value),
-            None => Configuration::default(),
-        }
+    pub fn configuration(&self) -> Result<Configuration, config::ConfigurationError> {
+        let value = self
+            .discount_node
+            .metafield
+            .as_ref()
+            .map(|metafield| metafield.value.as_str());
+        config::load(value, config::Mode::FailOpen)
     }
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let input: input::Input = serde_json::from_reader(std::io::BufReader::new(std::io::stdin()))?;
-    let mut out = std::io::stdout();
-    let mut serializer = serde_json::Serializer::new(&mut out);
-    function(input)?.serialize(&mut serializer)?;
-    Ok(())
+fn main() -> std::process::ExitCode {
+    shopify_function_core::run(function)
 }
 
-fn function(input: input::Input) -> Result<FunctionResult, Box<dyn std::error::Error>> {
-    let _config: Configuration = input.configuration();
+pub(crate) fn function(input: input::Input) -> Result<FunctionResult, Box<dyn std::error::Error>> {
+    let _config: Configuration = input.configuration()?;
     Ok(FunctionResult {
         discounts: vec![],
         discount_application_strategy: DiscountApplicationStrategy::First,
@@ -41,6 +42,12 @@ fn function(input: input::Input) -> Result<FunctionResult, Box<dyn std::error::E
 #[cfg(test)]
 mod tests {
     use super::*;
+    use shopify_function_core::fixtures;
+    use shopify_function_core::validate;
+
+    fn function(input: input::Input) -> Result<FunctionResult, Box<dyn std::error::Error>> {
+        validate::checked(include_str!("../schema.graphql"), super::function, input)
+    }
 
     fn input(configuration: Option<Configuration>) -> input::Input {
         let discount_node = input::DiscountNode {
@@ -49,7 +56,10 @@ mod tests {
                 input::Metafield { value }
             }),
         };
-        input::Input { discount_node }
+        input::Input {
+            cart: input::Cart { lines: vec![] },
+            discount_node,
+        }
     }
 
     #[test]
@@ -75,4 +85,9 @@ mod tests {
         });
         assert_eq!(handle_result, expected_handle_result);
     }
+
+    #[test]
+    fn test_fixtures() {
+        fixtures::check("tests/fixtures", function);
+    }
 }
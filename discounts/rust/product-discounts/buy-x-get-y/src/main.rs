@@ -0,0 +1,541 @@
+use serde::{Deserialize, Serialize};
+use shopify_function_core::config;
+use shopify_function_core::product_discounts::*;
+
+mod input {
+    #![allow(dead_code)]
+    include!(concat!(env!("OUT_DIR"), "/input.rs"));
+}
+
+#[cfg(test)]
+mod properties;
+
+/// The variants a customer has to buy, or gets discounted, in each bundle.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Items {
+    #[serde(default)]
+    pub variant_ids: Vec<ID>,
+    #[serde(default)]
+    pub product_ids: Vec<ID>,
+    pub quantity: Int,
+}
+
+impl Items {
+    fn contains(&self, variant: &input::Merchandise) -> bool {
+        variant
+            .id
+            .as_ref()
+            .is_some_and(|id| self.variant_ids.contains(id))
+            || variant
+                .product
+                .as_ref()
+                .is_some_and(|product| self.product_ids.contains(&product.id))
+    }
+}
+
+impl Default for Items {
+    fn default() -> Self {
+        Items {
+            variant_ids: vec![],
+            product_ids: vec![],
+            quantity: 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiscountType {
+    #[default]
+    Percentage,
+    /// Takes `value` off each discounted item.
+    FixedAmount,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Configuration {
+    pub buy: Items,
+    pub get: Items,
+    pub value: f64,
+    #[serde(default)]
+    pub discount_type: DiscountType,
+}
+
+impl Configuration {
+    pub const DEFAULT_VALUE: f64 = 100.0;
+
+    fn discount_value(&self) -> Result<Value, Box<dyn std::error::Error>> {
+        Ok(match self.discount_type {
+            DiscountType::Percentage => Value::Percentage(Percentage { value: self.value }),
+            DiscountType::FixedAmount => Value::FixedAmount(FixedAmount {
+                amount: Decimal::try_from(self.value)?,
+                applies_to_each_item: Some(true),
+            }),
+        })
+    }
+}
+
+impl Default for Configuration {
+    fn default() -> Self {
+        Configuration {
+            buy: Items::default(),
+            get: Items::default(),
+            value: Self::DEFAULT_VALUE,
+            discount_type: DiscountType::Percentage,
+        }
+    }
+}
+
+impl config::Validate for Configuration {
+    fn validate(&self) -> Result<(), config::ConfigurationError> {
+        match self.discount_type {
+            DiscountType::Percentage => config::validate_percentage("value", self.value)?,
+            DiscountType::FixedAmount => config::validate_non_negative("value", self.value)?,
+        }
+        for (field, items) in [("buy.quantity", &self.buy), ("get.quantity", &self.get)] {
+            if items.quantity <= 0 {
+                return Err(config::ConfigurationError::OutOfRange {
+                    field,
+                    value: items.quantity.into(),
+                    expected: "a quantity greater than 0",
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl input::Input {
+    pub fn configuration(&self) -> Result<Configuration, config::ConfigurationError> {
+        let value = self
+            .discount_node
+            .metafield
+            .as_ref()
+            .map(|metafield| metafield.value.as_str());
+        config::load(value, config::Mode::FailOpen)
+    }
+}
+
+fn main() -> std::process::ExitCode {
+    shopify_function_core::run(function)
+}
+
+pub(crate) fn function(input: input::Input) -> Result<FunctionResult, Box<dyn std::error::Error>> {
+    let config = input.configuration()?;
+    let items = cart_items(&input.cart.lines, &config);
+    let bundles = bundles(&items, config.buy.quantity, config.get.quantity);
+    let targets = targets(&items, bundles.saturating_mul(config.get.quantity));
+    Ok(build_result(config.discount_value()?, targets))
+}
+
+/// The units of a variant in the cart, and which side of the bundle they can
+/// be used for. A variant can be on both sides.
+#[derive(Clone, Debug, PartialEq)]
+struct CartItem<'a> {
+    id: &'a ID,
+    quantity: Int,
+    buy: bool,
+    get: bool,
+}
+
+fn cart_items<'a>(cart_lines: &'a [input::CartLine], config: &Configuration) -> Vec<CartItem<'a>> {
+    let mut items: Vec<CartItem> = vec![];
+    for line in cart_lines {
+        let id = match &line.merchandise.id {
+            Some(id) if line.quantity > 0 => id,
+            _ => continue,
+        };
+        let buy = config.buy.contains(&line.merchandise);
+        let get = config.get.contains(&line.merchandise);
+        if !buy && !get {
+            continue;
+        }
+        match items.iter_mut().find(|item| item.id == id) {
+            Some(item) => item.quantity = item.quantity.saturating_add(line.quantity),
+            None => items.push(CartItem {
+                id,
+                quantity: line.quantity,
+                buy,
+                get,
+            }),
+        }
+    }
+    items
+}
+
+/// The number of complete bundles: each one needs `buy_quantity` units to buy
+/// and `get_quantity` other units to discount.
+fn bundles(items: &[CartItem], buy_quantity: Int, get_quantity: Int) -> Int {
+    let units = |buy, get| -> i64 {
+        items
+            .iter()
+            .filter(|item| item.buy == buy && item.get == get)
+            .map(|item| i64::from(item.quantity))
+            .sum()
+    };
+    let buy_only = units(true, false);
+    let get_only = units(false, true);
+    let both = units(true, true);
+    let (buy_quantity, get_quantity) = (i64::from(buy_quantity), i64::from(get_quantity));
+
+    // Units on both sides make up for whatever the one-sided units can't
+    // cover, but each of them only counts once.
+    let bundles = ((buy_only + both) / buy_quantity)
+        .min((get_only + both) / get_quantity)
+        .min((buy_only + get_only + both) / (buy_quantity + get_quantity));
+    Int::try_from(bundles.max(0)).unwrap_or(Int::MAX)
+}
+
+/// Discounts `quantity` units, using units that can't be bought for the
+/// bundle first.
+fn targets(items: &[CartItem], quantity: Int) -> Vec<Target> {
+    let mut remaining = quantity;
+    let mut targets: Vec<Target> = vec![];
+    for item in items
+        .iter()
+        .filter(|item| item.get && !item.buy)
+        .chain(items.iter().filter(|item| item.get && item.buy))
+    {
+        let quantity = item.quantity.min(remaining);
+        if quantity <= 0 {
+            break;
+        }
+        remaining -= quantity;
+        targets.push(Target::ProductVariant {
+            id: item.id.to_string(),
+            quantity: Some(quantity),
+        });
+    }
+    targets
+}
+
+fn build_result(value: Value, targets: Vec<Target>) -> FunctionResult {
+    let discounts = if targets.is_empty() {
+        vec![]
+    } else {
+        vec![Discount {
+            message: None,
+            conditions: None,
+            targets,
+            value,
+        }]
+    };
+    FunctionResult {
+        discounts,
+        discount_application_strategy: DiscountApplicationStrategy::First,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shopify_function_core::fixtures;
+    use shopify_function_core::validate;
+
+    fn function(input: input::Input) -> Result<FunctionResult, Box<dyn std::error::Error>> {
+        validate::checked(include_str!("../schema.graphql"), super::function, input)
+    }
+
+    fn cart_line(variant: &str, product: &str, quantity: Int) -> input::CartLine {
+        input::CartLine {
+            quantity,
+            merchandise: input::Merchandise {
+                id: Some(format!("gid://shopify/ProductVariant/{}", variant)),
+                product: Some(input::Product {
+                    id: format!("gid://shopify/Product/{}", product),
+                }),
+            },
+        }
+    }
+
+    fn items(variants: &[&str], quantity: Int) -> Items {
+        Items {
+            variant_ids: variants
+                .iter()
+                .map(|variant| format!("gid://shopify/ProductVariant/{}", variant))
+                .collect(),
+            product_ids: vec![],
+            quantity,
+        }
+    }
+
+    fn input(configuration: Option<Configuration>, lines: Vec<input::CartLine>) -> input::Input {
+        let discount_node = input::DiscountNode {
+            metafield: configuration.map(|value| {
+                let value = serde_json::to_string(&value).unwrap();
+                input::Metafield { value }
+            }),
+        };
+        input::Input {
+            cart: input::Cart { lines },
+            discount_node,
+        }
+    }
+
+    /// Buy 2 of variant 0, get 1 of variant 1 for free.
+    fn buy_two_get_one() -> Configuration {
+        Configuration {
+            buy: items(&["0"], 2),
+            get: items(&["1"], 1),
+            ..Configuration::default()
+        }
+    }
+
+    #[test]
+    fn test_discount_with_no_configuration() {
+        let input = input(None, vec![cart_line("0", "0", 2), cart_line("1", "1", 1)]);
+        let result = serde_json::json!(function(input).unwrap());
+
+        let expected_result = serde_json::json!({
+            "discounts": [],
+            "discountApplicationStrategy": "FIRST",
+        });
+        assert_eq!(result, expected_result);
+    }
+
+    #[test]
+    fn test_discount_with_one_bundle() {
+        let input = input(
+            Some(buy_two_get_one()),
+            vec![cart_line("0", "0", 2), cart_line("1", "1", 3)],
+        );
+        let result = serde_json::json!(function(input).unwrap());
+
+        let expected_result = serde_json::json!({
+            "discounts": [{
+                "targets": [
+                    { "productVariant": { "id": "gid://shopify/ProductVariant/1", "quantity": 1 } },
+                ],
+                "value": { "percentage": { "value": 100.0 } },
+            }],
+            "discountApplicationStrategy": "FIRST",
+        });
+        assert_eq!(result, expected_result);
+    }
+
+    #[test]
+    fn test_discount_with_partial_matches() {
+        for lines in [
+            vec![cart_line("0", "0", 1), cart_line("1", "1", 1)],
+            vec![cart_line("0", "0", 2)],
+            vec![cart_line("1", "1", 5)],
+        ] {
+            let result = function(input(Some(buy_two_get_one()), lines)).unwrap();
+
+            assert!(result.discounts.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_discount_with_multiple_bundles() {
+        let input = input(
+            Some(buy_two_get_one()),
+            vec![
+                cart_line("0", "0", 3),
+                cart_line("1", "1", 1),
+                cart_line("0", "0", 2),
+                cart_line("1", "1", 5),
+            ],
+        );
+        let result = function(input).unwrap();
+
+        assert_eq!(
+            result.discounts[0].targets,
+            vec![Target::ProductVariant {
+                id: "gid://shopify/ProductVariant/1".to_string(),
+                quantity: Some(2),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_discount_with_overlapping_buy_and_get() {
+        let configuration = Configuration {
+            buy: items(&["0"], 1),
+            get: items(&["0"], 1),
+            ..Configuration::default()
+        };
+        for (quantity, discounted) in [(1, 0), (2, 1), (3, 1), (4, 2)] {
+            let input = input(
+                Some(configuration.clone()),
+                vec![cart_line("0", "0", quantity)],
+            );
+            let result = function(input).unwrap();
+
+            let targets: Vec<Target> = result
+                .discounts
+                .into_iter()
+                .flat_map(|discount| discount.targets)
+                .collect();
+            let expected_targets: Vec<Target> = (discounted > 0)
+                .then(|| Target::ProductVariant {
+                    id: "gid://shopify/ProductVariant/0".to_string(),
+                    quantity: Some(discounted),
+                })
+                .into_iter()
+                .collect();
+            assert_eq!(targets, expected_targets, "quantity {}", quantity);
+        }
+    }
+
+    #[test]
+    fn test_discount_with_large_quantities() {
+        let configuration = Configuration {
+            buy: items(&["0"], 2),
+            get: items(&["1"], 1),
+            ..Configuration::default()
+        };
+        let result = function(input(
+            Some(configuration),
+            vec![
+                cart_line("0", "0", 2_000_000_000),
+                cart_line("1", "1", 1_500_000_000),
+            ],
+        ))
+        .unwrap();
+
+        assert_eq!(
+            result.discounts[0].targets,
+            vec![Target::ProductVariant {
+                id: "gid://shopify/ProductVariant/1".to_string(),
+                quantity: Some(1_000_000_000),
+            }]
+        );
+
+        let configuration = Configuration {
+            buy: items(&["0"], 1),
+            get: items(&["0"], 1),
+            ..Configuration::default()
+        };
+        let result = function(input(
+            Some(configuration),
+            vec![cart_line("0", "0", Int::MAX)],
+        ))
+        .unwrap();
+
+        assert_eq!(
+            result.discounts[0].targets,
+            vec![Target::ProductVariant {
+                id: "gid://shopify/ProductVariant/0".to_string(),
+                quantity: Some(Int::MAX / 2),
+            }]
+        );
+
+        // More bundles than an `Int` holds, each discounting two units.
+        let configuration = Configuration {
+            buy: items(&["0", "1"], 1),
+            get: items(&["2", "3", "4", "5"], 2),
+            ..Configuration::default()
+        };
+        let lines = ["0", "1", "2", "3", "4", "5"]
+            .iter()
+            .map(|variant| cart_line(variant, variant, Int::MAX))
+            .collect();
+        let result = function(input(Some(configuration), lines)).unwrap();
+
+        assert_eq!(
+            result.discounts[0].targets,
+            vec![Target::ProductVariant {
+                id: "gid://shopify/ProductVariant/2".to_string(),
+                quantity: Some(Int::MAX),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_discount_ignores_lines_without_units() {
+        let result = function(input(
+            Some(buy_two_get_one()),
+            vec![
+                cart_line("0", "0", 2),
+                cart_line("1", "1", -3),
+                cart_line("1", "1", 1),
+            ],
+        ))
+        .unwrap();
+
+        assert_eq!(
+            result.discounts[0].targets,
+            vec![Target::ProductVariant {
+                id: "gid://shopify/ProductVariant/1".to_string(),
+                quantity: Some(1),
+            }]
+        );
+
+        let result = function(input(
+            Some(buy_two_get_one()),
+            vec![cart_line("0", "0", -4), cart_line("1", "1", 1)],
+        ))
+        .unwrap();
+
+        assert!(result.discounts.is_empty());
+    }
+
+    #[test]
+    fn test_discount_prefers_get_only_units() {
+        // Variant 0 can be bought or discounted, variant 1 can only be discounted.
+        let configuration = Configuration {
+            buy: items(&["0"], 2),
+            get: items(&["0", "1"], 1),
+            ..Configuration::default()
+        };
+        let input = input(
+            Some(configuration),
+            vec![cart_line("0", "0", 5), cart_line("1", "1", 1)],
+        );
+        let result = function(input).unwrap();
+
+        assert_eq!(
+            result.discounts[0].targets,
+            vec![
+                Target::ProductVariant {
+                    id: "gid://shopify/ProductVariant/1".to_string(),
+                    quantity: Some(1),
+                },
+                Target::ProductVariant {
+                    id: "gid://shopify/ProductVariant/0".to_string(),
+                    quantity: Some(1),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_discount_with_product_ids_and_fixed_amount() {
+        let configuration = Configuration {
+            buy: Items {
+                product_ids: vec!["gid://shopify/Product/0".to_string()],
+                ..Items::default()
+            },
+            get: Items {
+                product_ids: vec!["gid://shopify/Product/1".to_string()],
+                ..Items::default()
+            },
+            value: 5.0,
+            discount_type: DiscountType::FixedAmount,
+        };
+        let input = input(
+            Some(configuration),
+            vec![cart_line("0", "0", 1), cart_line("2", "1", 1)],
+        );
+        let result = serde_json::json!(function(input).unwrap());
+
+        let expected_result = serde_json::json!({
+            "discounts": [{
+                "targets": [
+                    { "productVariant": { "id": "gid://shopify/ProductVariant/2", "quantity": 1 } },
+                ],
+                "value": { "fixedAmount": { "amount": "5", "appliesToEachItem": true } },
+            }],
+            "discountApplicationStrategy": "FIRST",
+        });
+        assert_eq!(result, expected_result);
+    }
+
+    #[test]
+    fn test_fixtures() {
+        fixtures::check("tests/fixtures", function);
+    }
+}
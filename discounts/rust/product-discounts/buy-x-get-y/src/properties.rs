@@ -0,0 +1,109 @@
+//! Properties of the bundles on random carts and configurations, including
+//! configurations the function has to fall back from.
+
+use proptest::prelude::*;
+use serde_json::{json, Value as Json};
+use shopify_function_core::{harness, strategies};
+
+use super::*;
+
+/// Items that may overlap with the other side of the bundle.
+fn items() -> impl Strategy<Value = Json> {
+    (
+        prop::collection::vec(strategies::gid("ProductVariant", 5), 0..3),
+        prop::collection::vec(strategies::gid("Product", 3), 0..2),
+        -1..4i32,
+    )
+        .prop_map(|(variant_ids, product_ids, quantity)| {
+            json!({ "variantIds": variant_ids, "productIds": product_ids, "quantity": quantity })
+        })
+}
+
+/// Configurations, some of them out of the accepted ranges.
+fn configuration() -> impl Strategy<Value = Json> {
+    (
+        items(),
+        items(),
+        -10.0..150.0f64,
+        prop::sample::select(vec!["percentage", "fixedAmount"]),
+    )
+        .prop_map(|(buy, get, value, discount_type)| {
+            json!({ "buy": buy, "get": get, "value": value, "discountType": discount_type })
+        })
+}
+
+fn input() -> impl Strategy<Value = input::Input> {
+    (
+        strategies::cart_lines(),
+        strategies::metafield(configuration()),
+    )
+        .prop_map(|(lines, metafield)| {
+            serde_json::from_value(json!({
+                "cart": { "lines": lines },
+                "discountNode": { "metafield": metafield },
+            }))
+            .expect("the input deserializes")
+        })
+}
+
+/// The units of the variant `id` in the cart.
+fn units(lines: &[input::CartLine], id: &ID) -> i64 {
+    lines
+        .iter()
+        .filter(|line| line.merchandise.id.as_ref() == Some(id))
+        .map(|line| i64::from(line.quantity.max(0)))
+        .sum()
+}
+
+#[test]
+fn test_results_are_valid() {
+    harness::check(include_str!("../schema.graphql"), input(), function);
+}
+
+proptest! {
+    #[test]
+    fn test_targets_are_get_variants_in_the_cart(input in input()) {
+        let config = input.configuration().unwrap();
+        let result = function(input.clone()).unwrap();
+
+        for discount in &result.discounts {
+            for Target::ProductVariant { id, quantity } in &discount.targets {
+                let line = input.cart.lines.iter().find(|line| line.merchandise.id.as_ref() == Some(id));
+                prop_assert!(line.is_some(), "{} isn't in the cart", id);
+                prop_assert!(config.get.contains(&line.unwrap().merchandise), "{} can't be discounted", id);
+                let quantity = i64::from(quantity.unwrap_or(Int::MAX));
+                prop_assert!(
+                    (1..=units(&input.cart.lines, id)).contains(&quantity),
+                    "{} units of {} are discounted",
+                    quantity,
+                    id
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_discounted_units_make_complete_bundles(input in input()) {
+        let config = input.configuration().unwrap();
+        let result = function(input.clone()).unwrap();
+
+        let discounted: i64 = result
+            .discounts
+            .iter()
+            .flat_map(|discount| &discount.targets)
+            .map(|Target::ProductVariant { quantity, .. }| i64::from(quantity.unwrap_or(0)))
+            .sum();
+        let (buy_quantity, get_quantity) = (i64::from(config.buy.quantity), i64::from(config.get.quantity));
+        prop_assert_eq!(discounted % get_quantity, 0);
+        // Every bundle needs units to buy on top of the discounted ones.
+        let eligible: i64 = input
+            .cart
+            .lines
+            .iter()
+            .filter(|line| config.buy.contains(&line.merchandise) || config.get.contains(&line.merchandise))
+            .filter(|line| line.merchandise.id.is_some())
+            .map(|line| i64::from(line.quantity.max(0)))
+            .sum();
+        prop_assert!(discounted + discounted / get_quantity * buy_quantity <= eligible);
+    }
+}
@@ -1,101 +0,0 @@
-#![allow(dead_code)]
-
-pub type Boolean = bool;
-pub type Float = f64;
-pub type Int = i32;
-pub type ID = String;
-
-pub mod input {
-    use serde::Deserialize;
-
-    #[derive(Clone, Debug, Deserialize)]
-    #[serde(rename_all(deserialize = "camelCase"))]
-    pub struct Input {
-        pub discount_node: DiscountNode,
-    }
-
-    #[derive(Clone, Debug, Deserialize, Default)]
-    pub struct DiscountNode {
-        pub metafield: Option<Metafield>,
-    }
-
-    #[derive(Clone, Debug, Deserialize, Default)]
-    pub struct Metafield {
-        pub value: String,
-    }
-}
-
-use serde::Serialize;
-use serde_with::skip_serializing_none;
-
-#[derive(Clone, Debug, Serialize)]
-#[serde(rename_all(serialize = "camelCase"))]
-pub struct FunctionResult {
-    pub discount_application_strategy: DiscountApplicationStrategy,
-    pub discounts: Vec<Discount>,
-}
-
-#[derive(Clone, Debug, Serialize)]
-#[serde(rename_all(serialize = "SCREAMING_SNAKE_CASE"))]
-pub enum DiscountApplicationStrategy {
-    First,
-    Maximum,
-}
-
-#[skip_serializing_none]
-#[derive(Clone, Debug, Serialize)]
-pub struct Discount {
-    pub value: Value,
-    pub targets: Vec<Target>,
-    pub message: Option<String>,
-    pub conditions: Option<Vec<Condition>>,
-}
-
-#[derive(Clone, Debug, Serialize)]
-#[serde(rename_all(serialize = "camelCase"))]
-pub enum Value {
-    FixedAmount(FixedAmount),
-    Percentage(Percentage),
-}
-
-#[derive(Clone, Debug, Serialize)]
-#[serde(rename_all(serialize = "camelCase"))]
-pub struct FixedAmount {
-    pub applies_to_each_item: Option<Boolean>,
-    pub value: Float,
-}
-
-#[derive(Clone, Debug, Serialize)]
-pub struct Percentage {
-    pub value: Float,
-}
-
-#[skip_serializing_none]
-#[derive(Clone, Debug, Serialize)]
-#[serde(rename_all(serialize = "camelCase"))]
-pub enum Target {
-    ProductVariant { id: ID, quantity: Option<Int> },
-}
-
-#[derive(Clone, Debug, Serialize)]
-#[serde(rename_all(serialize = "camelCase"))]
-pub enum Condition {
-    #[serde(rename_all(serialize = "camelCase"))]
-    ProductMinimumQuantity {
-        ids: Vec<ID>,
-        minimum_quantity: Int,
-        target_type: ConditionTargetType,
-    },
-    #[serde(rename_all(serialize = "camelCase"))]
-    ProductMinimumSubtotal {
-        ids: Vec<ID>,
-        minimum_amount: Float,
-        target_type: ConditionTargetType,
-    },
-}
-
-#[derive(Clone, Debug, Serialize)]
-#[serde(rename_all(serialize = "SCREAMING_SNAKE_CASE"))]
-pub enum ConditionTargetType {
-    ProductVariant,
-}
@@ -0,0 +1,27 @@
+//! Properties of the function on random carts and configurations, including
+//! malformed configurations it has to fall back from.
+
+use proptest::prelude::*;
+use serde_json::json;
+use shopify_function_core::{harness, strategies};
+
+use super::*;
+
+fn input() -> impl Strategy<Value = input::Input> {
+    (
+        strategies::cart_lines(),
+        strategies::metafield(Just(json!({}))),
+    )
+        .prop_map(|(lines, metafield)| {
+            serde_json::from_value(json!({
+                "cart": { "lines": lines },
+                "discountNode": { "metafield": metafield },
+            }))
+            .expect("the input deserializes")
+        })
+}
+
+#[test]
+fn test_results_are_valid() {
+    harness::check(include_str!("../schema.graphql"), input(), function);
+}
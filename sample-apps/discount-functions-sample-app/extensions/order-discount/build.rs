@@ -0,0 +1,7 @@
+fn main() {
+    shopify_function_codegen::Codegen::default().write_input_types(
+        "schema.graphql",
+        "input.graphql",
+        "input.rs",
+    );
+}
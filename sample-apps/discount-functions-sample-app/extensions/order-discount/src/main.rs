@@ -1,20 +1,239 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
+use shopify_function_core::config;
+use shopify_function_core::messages::Messages;
+use shopify_function_core::money::{Money, MoneyError, Presentment, Rounding};
+use shopify_function_core::order_discounts::*;
+use shopify_function_core::CurrencyCode;
+
+mod input {
+    #![allow(dead_code)]
+    include!(concat!(env!("OUT_DIR"), "/input.rs"));
+}
+
+#[cfg(test)]
+mod properties;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Strategy {
+    #[default]
+    First,
+    Maximum,
+}
+
+impl From<Strategy> for DiscountApplicationStrategy {
+    fn from(strategy: Strategy) -> Self {
+        match strategy {
+            Strategy::First => DiscountApplicationStrategy::First,
+            Strategy::Maximum => DiscountApplicationStrategy::Maximum,
+        }
+    }
+}
+
+/// The order subtotal without the excluded variants. Discounts apply to it and
+/// their minimum subtotal is checked against it, so the target and condition
+/// always exclude the same variants.
+struct OrderSubtotal<'a> {
+    excluded_variant_ids: &'a [ID],
+}
+
+impl OrderSubtotal<'_> {
+    fn target(&self) -> Target {
+        Target::OrderSubtotal {
+            excluded_variant_ids: self.excluded_variant_ids.to_vec(),
+        }
+    }
+
+    fn minimum(&self, minimum_amount: f64) -> Condition {
+        Condition::OrderMinimumSubtotal(OrderMinimumSubtotal {
+            excluded_variant_ids: self.excluded_variant_ids.to_vec(),
+            minimum_amount,
+            target_type: TargetType::OrderSubtotal,
+        })
+    }
+}
+
+/// A percentage off the order once it reaches a subtotal and/or quantity.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tier {
+    pub value: f64,
+    #[serde(default)]
+    pub minimum_subtotal: Option<f64>,
+    #[serde(default)]
+    pub minimum_quantity: Option<Int>,
+}
+
+impl Tier {
+    /// The tier's minimums, or `None` for a tier that always applies.
+    fn conditions(&self, subtotal: &OrderSubtotal, variant_ids: &[ID]) -> Option<Vec<Condition>> {
+        let mut conditions = vec![];
+        if let Some(minimum_amount) = self.minimum_subtotal {
+            conditions.push(subtotal.minimum(minimum_amount));
+        }
+        if let Some(minimum_quantity) = self.minimum_quantity {
+            conditions.push(Condition::ProductMinimumQuantity(ProductMinimumQuantity {
+                ids: variant_ids.to_vec(),
+                minimum_quantity,
+                target_type: TargetType::ProductVariant,
+            }));
+        }
+        (!conditions.is_empty()).then_some(conditions)
+    }
+}
 
-mod api;
-use api::*;
+/// Which customers get the discount. Anonymous buyers only pass when no rule
+/// is set, and neither do customers whose amount spent is in another currency
+/// than the minimum.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Eligibility {
+    #[serde(default)]
+    pub minimum_number_of_orders: Option<Int>,
+    #[serde(default)]
+    pub minimum_amount_spent: Option<f64>,
+    #[serde(default)]
+    pub first_order_only: bool,
+}
+
+impl Eligibility {
+    fn is_empty(&self) -> bool {
+        self.minimum_number_of_orders.is_none()
+            && self.minimum_amount_spent.is_none()
+            && !self.first_order_only
+    }
 
+    fn is_eligible(&self, customer: Option<&input::Customer>, currency_code: CurrencyCode) -> bool {
+        let customer = match customer {
+            Some(customer) => customer,
+            None => return self.is_empty(),
+        };
+        if self.first_order_only && customer.number_of_orders > 0 {
+            return false;
+        }
+        if let Some(minimum) = self.minimum_number_of_orders {
+            if customer.number_of_orders < minimum {
+                return false;
+            }
+        }
+        if let Some(minimum) = self.minimum_amount_spent {
+            let amount_spent = Money::new(
+                customer.amount_spent.amount,
+                customer.amount_spent.currency_code,
+            );
+            match Decimal::try_from(minimum) {
+                Ok(minimum) if amount_spent >= Money::new(minimum, currency_code) => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+/// Amounts in a currency, instead of the ones converted from the shop's.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrencyOverrides {
+    #[serde(default)]
+    pub minimum_subtotal: Option<f64>,
+    /// Replaces every tier, e.g. for round minimums in the currency.
+    #[serde(default)]
+    pub tiers: Option<Vec<Tier>>,
+}
+
+/// The minimum subtotals and amount spent are in the shop's currency,
+/// converted to the currency the buyer pays in.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Configuration {
     pub value: f64,
     pub excluded_variant_ids: Vec<ID>,
+    /// Only discount orders whose subtotal, without the excluded variants,
+    /// reaches this amount, e.g. 15% off orders over $100.
+    #[serde(default)]
+    pub minimum_subtotal: Option<f64>,
+    /// Replaces `value` and `minimumSubtotal` with one discount per tier when
+    /// not empty.
+    #[serde(default)]
+    pub tiers: Vec<Tier>,
+    #[serde(default)]
+    pub discount_application_strategy: Strategy,
+    #[serde(default)]
+    pub eligibility: Eligibility,
+    /// Only customers with one of these tags get the discount, when not empty.
+    ///
+    /// This is also the variable of the input query, which is why it sits at
+    /// the top level of the configuration rather than in `eligibility`.
+    #[serde(default)]
+    pub customer_tags: Vec<String>,
+    /// Amounts for some currencies, e.g. `{ "JPY": { "minimumSubtotal": 15000 } }`.
+    #[serde(default)]
+    pub currencies: HashMap<CurrencyCode, CurrencyOverrides>,
+    /// How amounts converted from the shop's currency are rounded.
+    #[serde(default)]
+    pub rounding: Rounding,
+    /// The message per language, e.g. `"{percent}% off orders over {minimum}"`.
+    #[serde(default)]
+    pub messages: Messages,
 }
 
 impl Configuration {
     pub const DEFAULT_VALUE: f64 = 50.0;
 
-    fn from_str(str: &str) -> Self {
-        serde_json::from_str(str).expect("Unable to parse configuration value from metafield")
+    /// Whether the `customer` gets the discount, with the minimum amount spent
+    /// in `currency_code`.
+    fn is_eligible(&self, customer: Option<&input::Customer>, currency_code: CurrencyCode) -> bool {
+        let has_customer_tag =
+            self.customer_tags.is_empty() || customer.is_some_and(|customer| customer.has_any_tag);
+        has_customer_tag && self.eligibility.is_eligible(customer, currency_code)
+    }
+
+    /// The configuration with its amounts in the presentment currency.
+    fn in_currency(self, presentment: &Presentment) -> Result<Self, MoneyError> {
+        let overrides = self
+            .currencies
+            .get(&presentment.currency_code)
+            .cloned()
+            .unwrap_or_default();
+        let convert = |amount: Option<f64>| {
+            amount
+                .map(|amount| {
+                    let amount = Decimal::try_from(amount)
+                        .map_err(|_| MoneyError::Parse(amount.to_string()))?;
+                    let converted = presentment.convert(amount, self.rounding)?;
+                    f64::try_from(converted.amount).map_err(|_| MoneyError::Overflow)
+                })
+                .transpose()
+        };
+        let minimum_subtotal = match overrides.minimum_subtotal {
+            Some(minimum_subtotal) => Some(minimum_subtotal),
+            None => convert(self.minimum_subtotal)?,
+        };
+        let tiers = match overrides.tiers {
+            Some(tiers) => tiers,
+            None => self
+                .tiers
+                .iter()
+                .map(|tier| {
+                    Ok(Tier {
+                        minimum_subtotal: convert(tier.minimum_subtotal)?,
+                        ..tier.clone()
+                    })
+                })
+                .collect::<Result<_, MoneyError>>()?,
+        };
+        let eligibility = Eligibility {
+            minimum_amount_spent: convert(self.eligibility.minimum_amount_spent)?,
+            ..self.eligibility.clone()
+        };
+        Ok(Configuration {
+            minimum_subtotal,
+            tiers,
+            eligibility,
+            ..self
+        })
     }
 }
 
@@ -23,47 +242,168 @@ impl Default for Configuration {
         Configuration {
             value: Self::DEFAULT_VALUE,
             excluded_variant_ids: vec![],
+            minimum_subtotal: None,
+            tiers: vec![],
+            discount_application_strategy: Strategy::First,
+            eligibility: Eligibility::default(),
+            customer_tags: vec![],
+            currencies: HashMap::new(),
+            rounding: Rounding::default(),
+            messages: Messages::default(),
         }
     }
 }
 
-impl input::Input {
-    pub fn configuration(&self) -> Configuration {
-        match &self.discount_node.metafield {
-            Some(input::Metafield { value }) => Configuration::from_str(value),
-            None => Configuration::default(),
+impl config::Validate for Tier {
+    fn validate(&self) -> Result<(), config::ConfigurationError> {
+        config::validate_percentage("tiers.value", self.value)?;
+        if let Some(minimum_subtotal) = self.minimum_subtotal {
+            config::validate_non_negative("tiers.minimumSubtotal", minimum_subtotal)?;
+        }
+        if let Some(minimum_quantity) = self.minimum_quantity {
+            config::validate_non_negative("tiers.minimumQuantity", minimum_quantity.into())?;
         }
+        Ok(())
     }
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let input: input::Input = serde_json::from_reader(std::io::BufReader::new(std::io::stdin()))?;
-    let mut out = std::io::stdout();
-    let mut serializer = serde_json::Serializer::new(&mut out);
-    function(input)?.serialize(&mut serializer)?;
-    Ok(())
+impl config::Validate for Configuration {
+    fn validate(&self) -> Result<(), config::ConfigurationError> {
+        config::validate_percentage("value", self.value)?;
+        if let Some(minimum_subtotal) = self.minimum_subtotal {
+            config::validate_non_negative("minimumSubtotal", minimum_subtotal)?;
+        }
+        for overrides in self.currencies.values() {
+            if let Some(minimum_subtotal) = overrides.minimum_subtotal {
+                config::validate_non_negative("currencies.minimumSubtotal", minimum_subtotal)?;
+            }
+        }
+        let override_tiers = self
+            .currencies
+            .values()
+            .flat_map(|overrides| overrides.tiers.iter().flatten());
+        for tier in self.tiers.iter().chain(override_tiers) {
+            config::Validate::validate(tier)?;
+        }
+        if let Some(minimum) = self.eligibility.minimum_number_of_orders {
+            config::validate_non_negative("eligibility.minimumNumberOfOrders", minimum.into())?;
+        }
+        if let Some(minimum) = self.eligibility.minimum_amount_spent {
+            config::validate_non_negative("eligibility.minimumAmountSpent", minimum)?;
+        }
+        config::Validate::validate(&self.messages)
+    }
 }
 
-fn function(input: input::Input) -> Result<FunctionResult, Box<dyn std::error::Error>> {
-    let config = input.configuration();
+impl input::Input {
+    pub fn configuration(&self) -> Result<Configuration, config::ConfigurationError> {
+        let value = self
+            .discount_node
+            .metafield
+            .as_ref()
+            .map(|metafield| metafield.value.as_str());
+        config::load(value, config::Mode::FailOpen)
+    }
+
+    fn presentment(&self) -> Presentment {
+        Presentment::new(
+            self.cart.cost.subtotal_amount.currency_code,
+            self.presentment_currency_rate,
+        )
+    }
+}
+
+fn main() -> std::process::ExitCode {
+    shopify_function_core::run(function)
+}
+
+pub(crate) fn function(input: input::Input) -> Result<FunctionResult, Box<dyn std::error::Error>> {
+    let config = input.configuration()?.in_currency(&input.presentment())?;
+    let customer = input
+        .cart
+        .buyer_identity
+        .as_ref()
+        .and_then(|buyer_identity| buyer_identity.customer.as_ref());
+    let subtotal = OrderSubtotal {
+        excluded_variant_ids: &config.excluded_variant_ids,
+    };
+    let currency_code = input.cart.cost.subtotal_amount.currency_code;
+    let message = |value: f64, minimum_subtotal: Option<f64>| {
+        let mut values = vec![("percent", value.to_string())];
+        if let Some(minimum_subtotal) = minimum_subtotal {
+            let minor_units = currency_code.minor_units() as usize;
+            values.push(("minimum", format!("{:.*}", minor_units, minimum_subtotal)));
+        }
+        config
+            .messages
+            .render(input.localization.language.iso_code, &values)
+    };
+    let discounts = if !config.is_eligible(customer, currency_code) {
+        vec![]
+    } else if config.tiers.is_empty() {
+        let conditions = config
+            .minimum_subtotal
+            .map(|minimum_amount| vec![subtotal.minimum(minimum_amount)]);
+        let message = message(config.value, config.minimum_subtotal);
+        vec![discount(config.value, message, conditions, &subtotal)]
+    } else {
+        let variant_ids = variant_ids(&input.cart.lines, &config.excluded_variant_ids);
+        // With the `FIRST` strategy the first satisfied tier wins, so the
+        // biggest discounts go first.
+        let mut tiers = config.tiers.clone();
+        tiers.sort_by(|a, b| b.value.total_cmp(&a.value));
+        tiers
+            .iter()
+            .map(|tier| {
+                let conditions = tier.conditions(&subtotal, &variant_ids);
+                let message = message(tier.value, tier.minimum_subtotal);
+                discount(tier.value, message, conditions, &subtotal)
+            })
+            .collect()
+    };
     Ok(FunctionResult {
-        discounts: vec![Discount {
-            message: None,
-            conditions: None,
-            targets: vec![Target::OrderSubtotal {
-                excluded_variant_ids: config.excluded_variant_ids,
-            }],
-            value: Value::Percentage(Percentage {
-                value: config.value,
-            }),
-        }],
-        discount_application_strategy: DiscountApplicationStrategy::First,
+        discounts,
+        discount_application_strategy: config.discount_application_strategy.into(),
     })
 }
 
+fn discount(
+    value: f64,
+    message: Option<String>,
+    conditions: Option<Vec<Condition>>,
+    subtotal: &OrderSubtotal,
+) -> Discount {
+    Discount {
+        message,
+        conditions,
+        targets: vec![subtotal.target()],
+        value: Value::Percentage(Percentage { value }),
+    }
+}
+
+fn variant_ids(cart_lines: &[input::CartLine], excluded_variant_ids: &[ID]) -> Vec<ID> {
+    let mut variant_ids: Vec<ID> = vec![];
+    for id in cart_lines
+        .iter()
+        .filter_map(|line| line.merchandise.id.as_ref())
+    {
+        if !excluded_variant_ids.contains(id) && !variant_ids.contains(id) {
+            variant_ids.push(id.clone());
+        }
+    }
+    variant_ids
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use shopify_function_core::fixtures;
+    use shopify_function_core::messages::LanguageCode;
+    use shopify_function_core::validate;
+
+    fn function(input: input::Input) -> Result<FunctionResult, Box<dyn std::error::Error>> {
+        validate::checked(include_str!("../schema.graphql"), super::function, input)
+    }
 
     fn input(configuration: Option<Configuration>) -> input::Input {
         let discount_node = input::DiscountNode {
@@ -73,7 +413,162 @@ mod tests {
             }),
         };
 
-        input::Input { discount_node }
+        input::Input {
+            cart: input::Cart {
+                buyer_identity: None,
+                cost: input::CartCost {
+                    subtotal_amount: input::CartCostSubtotalAmount {
+                        currency_code: CurrencyCode::Usd,
+                    },
+                },
+                lines: vec![
+                    input::CartLine {
+                        merchandise: input::Merchandise {
+                            id: Some(String::from("gid://shopify/ProductVariant/0")),
+                        },
+                    },
+                    input::CartLine {
+                        merchandise: input::Merchandise {
+                            id: Some(String::from("gid://shopify/ProductVariant/1")),
+                        },
+                    },
+                ],
+            },
+            presentment_currency_rate: None,
+            localization: input::Localization {
+                language: input::Language {
+                    iso_code: LanguageCode::En,
+                },
+            },
+            discount_node,
+        }
+    }
+
+    /// The minimum subtotals of the discounts on a cart in `currency_code`, at
+    /// `rate` from the shop's currency.
+    fn minimum_subtotals(
+        configuration: &str,
+        currency_code: CurrencyCode,
+        rate: Option<&str>,
+    ) -> Vec<f64> {
+        let input = input::Input {
+            cart: input::Cart {
+                cost: input::CartCost {
+                    subtotal_amount: input::CartCostSubtotalAmount { currency_code },
+                },
+                ..input(None).cart
+            },
+            presentment_currency_rate: rate.map(|rate| rate.parse().unwrap()),
+            discount_node: input::DiscountNode {
+                metafield: Some(input::Metafield {
+                    value: configuration.to_string(),
+                }),
+            },
+            ..input(None)
+        };
+        function(input)
+            .unwrap()
+            .discounts
+            .into_iter()
+            .flat_map(|discount| discount.conditions.into_iter().flatten())
+            .filter_map(|condition| match condition {
+                Condition::OrderMinimumSubtotal(condition) => Some(condition.minimum_amount),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_minimum_subtotal_in_presentment_currency() {
+        let configuration = r#"{
+            "value": 15,
+            "excludedVariantIds": [],
+            "minimumSubtotal": 100,
+            "currencies": { "JPY": { "minimumSubtotal": 15000 } },
+            "rounding": "wholeUnit"
+        }"#;
+        let minimum_subtotals =
+            |currency_code, rate| minimum_subtotals(configuration, currency_code, rate);
+
+        assert_eq!(minimum_subtotals(CurrencyCode::Usd, None), vec![100.0]);
+        assert_eq!(
+            minimum_subtotals(CurrencyCode::Cad, Some("1.3456")),
+            vec![135.0]
+        );
+        assert_eq!(
+            minimum_subtotals(CurrencyCode::Eur, Some("0.9123")),
+            vec![91.0]
+        );
+        assert_eq!(
+            minimum_subtotals(CurrencyCode::Jpy, Some("134.567")),
+            vec![15000.0]
+        );
+    }
+
+    #[test]
+    fn test_tiers_in_presentment_currency() {
+        let configuration = r#"{
+            "value": 0,
+            "excludedVariantIds": [],
+            "tiers": [
+                { "value": 10, "minimumSubtotal": 100 },
+                { "value": 5, "minimumSubtotal": 50 }
+            ],
+            "currencies": {
+                "JPY": { "tiers": [{ "value": 10, "minimumSubtotal": 10000 }] }
+            },
+            "rounding": "ninetyNine"
+        }"#;
+        let minimum_subtotals =
+            |currency_code, rate| minimum_subtotals(configuration, currency_code, rate);
+
+        assert_eq!(
+            minimum_subtotals(CurrencyCode::Gbp, Some("0.7912")),
+            vec![79.99, 39.99]
+        );
+        assert_eq!(
+            minimum_subtotals(CurrencyCode::Kwd, Some("0.3071")),
+            vec![30.99, 15.99]
+        );
+        assert_eq!(
+            minimum_subtotals(CurrencyCode::Jpy, Some("134.567")),
+            vec![10000.0]
+        );
+    }
+
+    #[test]
+    fn test_minimum_amount_spent_in_presentment_currency() {
+        let configuration = Configuration {
+            eligibility: Eligibility {
+                minimum_amount_spent: Some(100.0),
+                ..Eligibility::default()
+            },
+            ..Configuration::default()
+        };
+        // 100 dollars are 135 Canadian dollars.
+        let is_discounted = |amount_spent: &str| {
+            let mut customer = customer(false, 1, amount_spent);
+            customer.amount_spent.currency_code = CurrencyCode::Cad;
+            let input = input::Input {
+                cart: input::Cart {
+                    buyer_identity: Some(input::BuyerIdentity {
+                        customer: Some(customer),
+                    }),
+                    cost: input::CartCost {
+                        subtotal_amount: input::CartCostSubtotalAmount {
+                            currency_code: CurrencyCode::Cad,
+                        },
+                    },
+                    ..input(None).cart
+                },
+                presentment_currency_rate: Some("1.35".parse().unwrap()),
+                ..input(Some(configuration.clone()))
+            };
+            !function(input).unwrap().discounts.is_empty()
+        };
+
+        assert!(!is_discounted("120.00"));
+        assert!(is_discounted("135.00"));
     }
 
     #[test]
@@ -95,7 +590,7 @@ mod tests {
     fn test_discount_with_value() {
         let input = input(Some(Configuration {
             value: 10.0,
-            excluded_variant_ids: vec![],
+            ..Configuration::default()
         }));
         let result = serde_json::json!(function(input).unwrap());
 
@@ -112,8 +607,8 @@ mod tests {
     #[test]
     fn test_discount_with_excluded_variant_ids() {
         let input = input(Some(Configuration {
-            value: Configuration::DEFAULT_VALUE,
             excluded_variant_ids: vec!["gid://shopify/ProductVariant/1".to_string()],
+            ..Configuration::default()
         }));
         let result = serde_json::json!(function(input).unwrap());
 
@@ -126,4 +621,345 @@ mod tests {
         });
         assert_eq!(result, expected_result);
     }
+
+    #[test]
+    fn test_discount_with_out_of_range_value() {
+        let input = input(Some(Configuration {
+            value: 150.0,
+            excluded_variant_ids: vec!["gid://shopify/ProductVariant/1".to_string()],
+            ..Configuration::default()
+        }));
+        let result = serde_json::json!(function(input).unwrap());
+
+        let expected_result = serde_json::json!({
+            "discounts": [{
+                "targets": [{ "orderSubtotal": { "excludedVariantIds": [] } }],
+                "value": { "percentage": { "value": 50.0 } },
+            }],
+            "discountApplicationStrategy": "FIRST",
+        });
+        assert_eq!(result, expected_result);
+    }
+
+    #[test]
+    fn test_discount_with_minimum_subtotal() {
+        let configuration: Configuration = serde_json::from_str(
+            r#"{
+                "value": 15,
+                "excludedVariantIds": ["gid://shopify/ProductVariant/1"],
+                "minimumSubtotal": 100
+            }"#,
+        )
+        .unwrap();
+        let result = serde_json::json!(function(input(Some(configuration))).unwrap());
+
+        let expected_result = serde_json::json!({
+            "discounts": [{
+                "targets": [{ "orderSubtotal": { "excludedVariantIds": ["gid://shopify/ProductVariant/1"] } }],
+                "value": { "percentage": { "value": 15.0 } },
+                "conditions": [{
+                    "orderMinimumSubtotal": {
+                        "excludedVariantIds": ["gid://shopify/ProductVariant/1"],
+                        "minimumAmount": 100.0,
+                        "targetType": "ORDER_SUBTOTAL",
+                    }
+                }],
+            }],
+            "discountApplicationStrategy": "FIRST",
+        });
+        assert_eq!(result, expected_result);
+    }
+
+    #[test]
+    fn test_negative_minimum_subtotal_is_invalid() {
+        let configuration = Configuration {
+            minimum_subtotal: Some(-1.0),
+            ..Configuration::default()
+        };
+        assert!(config::Validate::validate(&configuration).is_err());
+
+        let configuration: Configuration = serde_json::from_str(
+            r#"{
+                "value": 10,
+                "excludedVariantIds": [],
+                "currencies": { "EUR": { "tiers": [{ "value": 10, "minimumSubtotal": -1 }] } }
+            }"#,
+        )
+        .unwrap();
+        assert!(config::Validate::validate(&configuration).is_err());
+    }
+
+    #[test]
+    fn test_discount_with_tiers() {
+        let input = input(Some(Configuration {
+            excluded_variant_ids: vec!["gid://shopify/ProductVariant/1".to_string()],
+            tiers: vec![
+                Tier {
+                    value: 5.0,
+                    minimum_subtotal: Some(50.0),
+                    minimum_quantity: None,
+                },
+                Tier {
+                    value: 15.0,
+                    minimum_subtotal: Some(200.0),
+                    minimum_quantity: None,
+                },
+                Tier {
+                    value: 10.0,
+                    minimum_subtotal: None,
+                    minimum_quantity: Some(3),
+                },
+                Tier {
+                    value: 1.0,
+                    minimum_subtotal: None,
+                    minimum_quantity: None,
+                },
+            ],
+            discount_application_strategy: Strategy::Maximum,
+            ..Configuration::default()
+        }));
+        let result = serde_json::json!(function(input).unwrap());
+
+        let target = serde_json::json!({
+            "orderSubtotal": { "excludedVariantIds": ["gid://shopify/ProductVariant/1"] }
+        });
+        let minimum_subtotal = |minimum_amount: f64| {
+            serde_json::json!({
+                "orderMinimumSubtotal": {
+                    "excludedVariantIds": ["gid://shopify/ProductVariant/1"],
+                    "minimumAmount": minimum_amount,
+                    "targetType": "ORDER_SUBTOTAL",
+                }
+            })
+        };
+        let expected_result = serde_json::json!({
+            "discounts": [
+                {
+                    "targets": [target],
+                    "value": { "percentage": { "value": 15.0 } },
+                    "conditions": [minimum_subtotal(200.0)],
+                },
+                {
+                    "targets": [target],
+                    "value": { "percentage": { "value": 10.0 } },
+                    "conditions": [{
+                        "productMinimumQuantity": {
+                            "ids": ["gid://shopify/ProductVariant/0"],
+                            "minimumQuantity": 3,
+                            "targetType": "PRODUCT_VARIANT",
+                        }
+                    }],
+                },
+                {
+                    "targets": [target],
+                    "value": { "percentage": { "value": 5.0 } },
+                    "conditions": [minimum_subtotal(50.0)],
+                },
+                {
+                    "targets": [target],
+                    "value": { "percentage": { "value": 1.0 } },
+                },
+            ],
+            "discountApplicationStrategy": "MAXIMUM",
+        });
+        assert_eq!(result, expected_result);
+    }
+
+    #[test]
+    fn test_tiers_and_strategy_from_metafield() {
+        let configuration: Configuration = serde_json::from_str(
+            r#"{
+                "value": 0,
+                "excludedVariantIds": [],
+                "tiers": [{ "value": 5, "minimumSubtotal": 50 }],
+                "discountApplicationStrategy": "FIRST"
+            }"#,
+        )
+        .unwrap();
+        let result = function(input(Some(configuration))).unwrap();
+
+        assert_eq!(result.discounts.len(), 1);
+        assert_eq!(
+            result.discount_application_strategy,
+            DiscountApplicationStrategy::First
+        );
+    }
+
+    fn customer(has_any_tag: bool, number_of_orders: Int, amount_spent: &str) -> input::Customer {
+        input::Customer {
+            has_any_tag,
+            number_of_orders,
+            amount_spent: input::MoneyV2 {
+                amount: amount_spent.parse().unwrap(),
+                currency_code: CurrencyCode::Usd,
+            },
+        }
+    }
+
+    fn is_eligible(eligibility: Eligibility, customer: Option<input::Customer>) -> bool {
+        let input = input::Input {
+            cart: input::Cart {
+                buyer_identity: Some(input::BuyerIdentity { customer }),
+                ..input(None).cart
+            },
+            ..input(Some(Configuration {
+                eligibility,
+                ..Configuration::default()
+            }))
+        };
+        !function(input).unwrap().discounts.is_empty()
+    }
+
+    #[test]
+    fn test_discount_for_anonymous_buyers() {
+        assert!(is_eligible(Eligibility::default(), None));
+        assert!(!is_eligible(
+            Eligibility {
+                first_order_only: true,
+                ..Eligibility::default()
+            },
+            None
+        ));
+        assert!(!is_eligible(
+            Eligibility {
+                minimum_amount_spent: Some(0.0),
+                ..Eligibility::default()
+            },
+            None
+        ));
+    }
+
+    #[test]
+    fn test_discount_for_tagged_customers() {
+        let configuration: Configuration = serde_json::from_str(
+            r#"{ "value": 10, "excludedVariantIds": [], "customerTags": ["Wholesale"] }"#,
+        )
+        .unwrap();
+        let is_discounted = |configuration: &Configuration, customer| {
+            let input = input::Input {
+                cart: input::Cart {
+                    buyer_identity: Some(input::BuyerIdentity { customer }),
+                    ..input(None).cart
+                },
+                ..input(Some(configuration.clone()))
+            };
+            !function(input).unwrap().discounts.is_empty()
+        };
+
+        assert_eq!(configuration.customer_tags, vec!["Wholesale"]);
+        assert!(is_discounted(
+            &configuration,
+            Some(customer(true, 0, "0.0"))
+        ));
+        assert!(!is_discounted(
+            &configuration,
+            Some(customer(false, 0, "0.0"))
+        ));
+        assert!(!is_discounted(&configuration, None));
+
+        // Without tags, `hasAnyTag` is false for everyone and isn't required.
+        let configuration = Configuration::default();
+        assert!(is_discounted(
+            &configuration,
+            Some(customer(false, 0, "0.0"))
+        ));
+    }
+
+    #[test]
+    fn test_discount_for_returning_customers() {
+        let eligibility = Eligibility {
+            minimum_number_of_orders: Some(3),
+            minimum_amount_spent: Some(100.0),
+            ..Eligibility::default()
+        };
+
+        assert!(is_eligible(
+            eligibility.clone(),
+            Some(customer(false, 3, "100.0"))
+        ));
+        assert!(!is_eligible(
+            eligibility.clone(),
+            Some(customer(false, 2, "500.0"))
+        ));
+        assert!(!is_eligible(eligibility, Some(customer(false, 5, "99.99"))));
+    }
+
+    #[test]
+    fn test_amount_spent_in_another_currency() {
+        let eligibility = Eligibility {
+            minimum_amount_spent: Some(100.0),
+            ..Eligibility::default()
+        };
+        // 15000 yen can't be compared with the minimum in the cart's dollars.
+        let mut customer = customer(false, 3, "15000");
+        customer.amount_spent.currency_code = CurrencyCode::Jpy;
+
+        assert!(!is_eligible(eligibility, Some(customer)));
+    }
+
+    #[test]
+    fn test_discount_for_first_order_only() {
+        let eligibility = Eligibility {
+            first_order_only: true,
+            ..Eligibility::default()
+        };
+
+        assert!(is_eligible(
+            eligibility.clone(),
+            Some(customer(false, 0, "0.0"))
+        ));
+        assert!(!is_eligible(eligibility, Some(customer(false, 1, "10.0"))));
+    }
+
+    #[test]
+    fn test_tier_messages_in_buyer_language() {
+        let configuration = r#"{
+            "value": 0,
+            "excludedVariantIds": [],
+            "tiers": [
+                { "value": 10, "minimumSubtotal": 100 },
+                { "value": 5, "minimumSubtotal": 50 }
+            ],
+            "messages": {
+                "templates": {
+                    "EN": "{percent}% off orders over {minimum}",
+                    "DE": "{percent} % Rabatt ab {minimum}"
+                },
+                "fallbackLanguage": "EN"
+            }
+        }"#;
+        let messages = |iso_code| {
+            let input = input::Input {
+                localization: input::Localization {
+                    language: input::Language { iso_code },
+                },
+                discount_node: input::DiscountNode {
+                    metafield: Some(input::Metafield {
+                        value: configuration.to_string(),
+                    }),
+                },
+                ..input(None)
+            };
+            function(input)
+                .unwrap()
+                .discounts
+                .into_iter()
+                .map(|discount| discount.message.unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            messages(LanguageCode::De),
+            vec!["10 % Rabatt ab 100.00", "5 % Rabatt ab 50.00"]
+        );
+        assert_eq!(
+            messages(LanguageCode::Ja),
+            vec!["10% off orders over 100.00", "5% off orders over 50.00"]
+        );
+    }
+
+    #[test]
+    fn test_fixtures() {
+        fixtures::check("tests/fixtures", function);
+    }
 }
@@ -0,0 +1,203 @@
+//! Properties of the discount on random carts, buyers and configurations,
+//! including configurations the function has to fall back from.
+
+use proptest::prelude::*;
+use proptest::strategy::Strategy;
+use serde_json::{json, Value as Json};
+use shopify_function_core::{harness, strategies};
+
+use super::*;
+
+fn buyer_identity() -> impl Strategy<Value = Json> {
+    let customer = (
+        any::<bool>(),
+        0..20i32,
+        strategies::amount(),
+        prop::sample::select(&CURRENCY_CODES[..]),
+    )
+        .prop_map(|(has_any_tag, number_of_orders, amount, currency_code)| {
+            json!({
+                "hasAnyTag": has_any_tag,
+                "numberOfOrders": number_of_orders,
+                "amountSpent": { "amount": amount, "currencyCode": currency_code },
+            })
+        });
+    prop_oneof![
+        Just(Json::Null),
+        prop::option::of(customer).prop_map(|customer| json!({ "customer": customer })),
+    ]
+}
+
+/// Configurations, some of them out of the accepted ranges.
+fn configuration() -> impl Strategy<Value = Json> {
+    let tier = (
+        -10.0..110.0f64,
+        prop::option::of(-10.0..500.0f64),
+        prop::option::of(-1..10i32),
+    )
+        .prop_map(|(value, minimum_subtotal, minimum_quantity)| {
+            json!({
+                "value": value,
+                "minimumSubtotal": minimum_subtotal,
+                "minimumQuantity": minimum_quantity,
+            })
+        });
+    let eligibility = (
+        prop::option::of(-1..10i32),
+        prop::option::of(0.0..1000.0f64),
+        any::<bool>(),
+    )
+        .prop_map(
+            |(minimum_number_of_orders, minimum_amount_spent, first_order_only)| {
+                json!({
+                    "minimumNumberOfOrders": minimum_number_of_orders,
+                    "minimumAmountSpent": minimum_amount_spent,
+                    "firstOrderOnly": first_order_only,
+                })
+            },
+        );
+    (
+        -10.0..110.0f64,
+        prop::collection::vec(strategies::gid("ProductVariant", 5), 0..3),
+        prop::option::of(-10.0..500.0f64),
+        prop::collection::vec(tier, 0..4),
+        prop::sample::select(vec!["FIRST", "MAXIMUM"]),
+        eligibility,
+        prop::sample::subsequence(vec!["VIP", "Wholesale"], 0..=2),
+        currencies(),
+        prop::sample::select(vec!["minorUnit", "wholeUnit", "ninetyNine"]),
+        strategies::messages(&["percent", "minimum"]),
+    )
+        .prop_map(
+            |(
+                value,
+                excluded_variant_ids,
+                minimum_subtotal,
+                tiers,
+                strategy,
+                eligibility,
+                customer_tags,
+                currencies,
+                rounding,
+                messages,
+            )| {
+                json!({
+                    "value": value,
+                    "excludedVariantIds": excluded_variant_ids,
+                    "minimumSubtotal": minimum_subtotal,
+                    "tiers": tiers,
+                    "discountApplicationStrategy": strategy,
+                    "eligibility": eligibility,
+                    "customerTags": customer_tags,
+                    "currencies": currencies,
+                    "rounding": rounding,
+                    "messages": messages,
+                })
+            },
+        )
+}
+
+const CURRENCY_CODES: [&str; 4] = ["USD", "CAD", "EUR", "JPY"];
+
+/// Overrides of the minimum subtotals for some currencies.
+fn currencies() -> impl Strategy<Value = Json> {
+    let tier = (-10.0..110.0f64, prop::option::of(-10.0..5000.0f64)).prop_map(
+        |(value, minimum_subtotal)| json!({ "value": value, "minimumSubtotal": minimum_subtotal }),
+    );
+    let overrides = (
+        prop::option::of(-10.0..5000.0f64),
+        prop::option::of(prop::collection::vec(tier, 0..3)),
+    )
+        .prop_map(|(minimum_subtotal, tiers)| {
+            json!({ "minimumSubtotal": minimum_subtotal, "tiers": tiers })
+        });
+    prop::collection::btree_map(prop::sample::select(&CURRENCY_CODES[..]), overrides, 0..3)
+        .prop_map(|currencies| json!(currencies))
+}
+
+fn input() -> impl Strategy<Value = input::Input> {
+    (
+        prop::sample::select(&CURRENCY_CODES[..]),
+        prop::option::of((1..200_000u32).prop_map(|rate| format!("{}", rate as f64 / 1000.0))),
+        buyer_identity(),
+        strategies::cart_lines(),
+        strategies::language_code(),
+        strategies::metafield(configuration()),
+    )
+        .prop_map(
+            |(currency_code, rate, buyer_identity, lines, language_code, metafield)| {
+                serde_json::from_value(json!({
+                    "cart": {
+                        "buyerIdentity": buyer_identity,
+                        "cost": { "subtotalAmount": { "currencyCode": currency_code } },
+                        "lines": lines,
+                    },
+                    "presentmentCurrencyRate": rate,
+                    "localization": { "language": { "isoCode": language_code } },
+                    "discountNode": { "metafield": metafield },
+                }))
+                .expect("the input deserializes")
+            },
+        )
+}
+
+#[test]
+fn test_results_are_valid() {
+    harness::check(include_str!("../schema.graphql"), input(), function);
+}
+
+proptest! {
+    #[test]
+    fn test_excluded_variants_are_never_counted(input in input()) {
+        let config = input.configuration().unwrap();
+        let result = function(input).unwrap();
+
+        for discount in &result.discounts {
+            for target in &discount.targets {
+                if let Target::OrderSubtotal { excluded_variant_ids } = target {
+                    prop_assert_eq!(excluded_variant_ids, &config.excluded_variant_ids);
+                }
+            }
+            for condition in discount.conditions.iter().flatten() {
+                match condition {
+                    Condition::OrderMinimumSubtotal(condition) => {
+                        prop_assert_eq!(&condition.excluded_variant_ids, &config.excluded_variant_ids);
+                    }
+                    Condition::ProductMinimumQuantity(condition) => {
+                        prop_assert!(condition.ids.iter().all(|id| !config.excluded_variant_ids.contains(id)));
+                    }
+                    Condition::ProductMinimumSubtotal(condition) => {
+                        prop_assert!(condition.ids.iter().all(|id| !config.excluded_variant_ids.contains(id)));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_ineligible_buyers_get_no_discount(input in input()) {
+        let config = input.configuration().unwrap().in_currency(&input.presentment()).unwrap();
+        let customer = input
+            .cart
+            .buyer_identity
+            .as_ref()
+            .and_then(|buyer_identity| buyer_identity.customer.clone());
+        let currency_code = input.cart.cost.subtotal_amount.currency_code;
+        let result = function(input).unwrap();
+
+        if !config.is_eligible(customer.as_ref(), currency_code) {
+            prop_assert!(result.discounts.is_empty());
+        } else {
+            prop_assert_eq!(result.discounts.len(), config.tiers.len().max(1));
+        }
+    }
+
+    #[test]
+    fn test_messages_are_filled_in(input in input()) {
+        let result = function(input).unwrap();
+
+        for message in result.discounts.iter().filter_map(|discount| discount.message.as_ref()) {
+            prop_assert!(!message.contains("{percent}"), "{}", message);
+        }
+    }
+}
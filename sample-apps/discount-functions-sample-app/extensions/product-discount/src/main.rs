@@ -1,20 +1,240 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
+use shopify_function_core::config;
+use shopify_function_core::messages::{LanguageCode, Messages};
+use shopify_function_core::money::{MoneyError, Presentment, Rounding};
+use shopify_function_core::product_discounts::*;
+use shopify_function_core::CurrencyCode;
+
+mod input {
+    #![allow(dead_code)]
+    include!(concat!(env!("OUT_DIR"), "/input.rs"));
+}
+
+mod markdown;
+#[cfg(test)]
+mod properties;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiscountType {
+    #[default]
+    Percentage,
+    FixedAmount,
+}
+
+/// Caps how many units are discounted, e.g. `{ "perVariant": 2 }`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QuantityLimit {
+    PerVariant(Int),
+    PerOrder(Int),
+}
 
-mod api;
-use api::*;
+/// Whether gift cards are discounted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GiftCards {
+    #[default]
+    Included,
+    Excluded,
+    Only,
+}
+
+/// The products the discount applies to. When any `included*` list is set,
+/// only products matching one of them are discounted, and products matching
+/// any `excluded*` list never are.
+///
+/// The collection and tag lists are also the variables of the input query,
+/// which is why they sit at the top level of the configuration.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProductRules {
+    pub included_collection_ids: Vec<ID>,
+    pub excluded_collection_ids: Vec<ID>,
+    pub included_tags: Vec<String>,
+    pub excluded_tags: Vec<String>,
+    pub included_vendors: Vec<String>,
+    pub excluded_vendors: Vec<String>,
+    pub included_product_types: Vec<String>,
+    pub excluded_product_types: Vec<String>,
+    pub gift_cards: GiftCards,
+}
+
+impl ProductRules {
+    fn applies_to(&self, product: Option<&input::Product>) -> bool {
+        let is_gift_card = product.is_some_and(|product| product.is_gift_card);
+        let gift_card_matches = match self.gift_cards {
+            GiftCards::Included => true,
+            GiftCards::Excluded => !is_gift_card,
+            GiftCards::Only => is_gift_card,
+        };
+        let has_included_rules = !(self.included_collection_ids.is_empty()
+            && self.included_tags.is_empty()
+            && self.included_vendors.is_empty()
+            && self.included_product_types.is_empty());
+        // The query answers for the lists in the metafield, which may not be
+        // the configuration in use if it was rejected, hence the list checks.
+        let included = product.is_some_and(|product| {
+            (!self.included_collection_ids.is_empty() && product.in_included_collection)
+                || (!self.included_tags.is_empty() && product.has_included_tag)
+                || contains(&self.included_vendors, &product.vendor)
+                || contains(&self.included_product_types, &product.product_type)
+        });
+        let excluded = product.is_some_and(|product| {
+            (!self.excluded_collection_ids.is_empty() && product.in_excluded_collection)
+                || (!self.excluded_tags.is_empty() && product.has_excluded_tag)
+                || contains(&self.excluded_vendors, &product.vendor)
+                || contains(&self.excluded_product_types, &product.product_type)
+        });
+        gift_card_matches && (included || !has_included_rules) && !excluded
+    }
+}
 
+fn contains(values: &[String], value: &Option<String>) -> bool {
+    value.as_ref().is_some_and(|value| values.contains(value))
+}
+
+/// Amounts in a currency, instead of the ones converted from the shop's.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrencyOverrides {
+    /// Only replaces a fixed amount.
+    #[serde(default)]
+    pub value: Option<f64>,
+    #[serde(default)]
+    pub minimum_subtotal: Option<f64>,
+}
+
+/// A fixed amount `value` and the `minimumSubtotal` are in the shop's currency,
+/// converted to the currency the buyer pays in.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Configuration {
     pub value: f64,
+    #[serde(default)]
+    pub discount_type: DiscountType,
+    #[serde(default)]
+    pub applies_to_each_item: bool,
     pub excluded_variant_ids: Vec<ID>,
+    #[serde(default)]
+    pub quantity_limit: Option<QuantityLimit>,
+    /// Only apply the discount when at least this many eligible items are bought.
+    #[serde(default)]
+    pub minimum_quantity: Option<Int>,
+    /// Only apply the discount when the eligible items cost at least this much.
+    #[serde(default)]
+    pub minimum_subtotal: Option<f64>,
+    #[serde(flatten)]
+    pub products: ProductRules,
+    /// Leave out the items already on sale, below their compare-at price.
+    #[serde(default)]
+    pub exclude_sale_items: bool,
+    /// The most an item can be marked down, its compare-at price and the
+    /// discount together, as a percentage of its compare-at price.
+    #[serde(default)]
+    pub maximum_markdown: Option<f64>,
+    /// Amounts for some currencies, e.g. `{ "JPY": { "value": 1500 } }`.
+    #[serde(default)]
+    pub currencies: HashMap<CurrencyCode, CurrencyOverrides>,
+    /// How amounts converted from the shop's currency are rounded.
+    #[serde(default)]
+    pub rounding: Rounding,
+    /// The message per language, e.g. `"{percent}% off"` or
+    /// `"{amount} {currency} off"`.
+    #[serde(default)]
+    pub messages: Messages,
 }
 
 impl Configuration {
     pub const DEFAULT_VALUE: f64 = 50.0;
 
-    fn from_str(str: &str) -> Self {
-        serde_json::from_str(str).expect("Unable to parse configuration value from metafield")
+    /// The configuration with its amounts in the presentment currency.
+    fn in_currency(self, presentment: &Presentment) -> Result<Self, MoneyError> {
+        let overrides = self
+            .currencies
+            .get(&presentment.currency_code)
+            .cloned()
+            .unwrap_or_default();
+        let convert = |amount: f64| {
+            let amount =
+                Decimal::try_from(amount).map_err(|_| MoneyError::Parse(amount.to_string()))?;
+            let converted = presentment.convert(amount, self.rounding)?;
+            f64::try_from(converted.amount).map_err(|_| MoneyError::Overflow)
+        };
+        let value = match (self.discount_type, overrides.value) {
+            (DiscountType::Percentage, _) => self.value,
+            (DiscountType::FixedAmount, Some(value)) => value,
+            (DiscountType::FixedAmount, None) => convert(self.value)?,
+        };
+        let minimum_subtotal = match (overrides.minimum_subtotal, self.minimum_subtotal) {
+            (Some(minimum_subtotal), _) => Some(minimum_subtotal),
+            (None, Some(minimum_subtotal)) => Some(convert(minimum_subtotal)?),
+            (None, None) => None,
+        };
+        Ok(Configuration {
+            value,
+            minimum_subtotal,
+            ..self
+        })
+    }
+
+    fn discount_value(&self) -> Result<Value, Box<dyn std::error::Error>> {
+        Ok(match self.discount_type {
+            DiscountType::Percentage => Value::Percentage(Percentage { value: self.value }),
+            DiscountType::FixedAmount => Value::FixedAmount(FixedAmount {
+                amount: Decimal::try_from(self.value)?,
+                applies_to_each_item: Some(self.applies_to_each_item),
+            }),
+        })
+    }
+
+    /// The message in `language` for the discount's `value`.
+    fn message(
+        &self,
+        value: &Value,
+        language: LanguageCode,
+        currency_code: CurrencyCode,
+    ) -> Option<String> {
+        let values = match value {
+            Value::Percentage(Percentage { value }) => vec![("percent", value.to_string())],
+            Value::FixedAmount(FixedAmount { amount, .. }) => {
+                let minor_units = currency_code.minor_units() as usize;
+                vec![
+                    ("amount", format!("{:.*}", minor_units, amount)),
+                    ("currency", currency_code.to_string()),
+                ]
+            }
+        };
+        self.messages.render(language, &values)
+    }
+
+    /// The minimums are checked against the variants being discounted.
+    fn conditions(&self, targets: &[Target]) -> Option<Vec<Condition>> {
+        let mut ids: Vec<ID> = vec![];
+        for Target::ProductVariant { id, .. } in targets {
+            if !ids.contains(id) {
+                ids.push(id.clone());
+            }
+        }
+
+        let mut conditions = vec![];
+        if let Some(minimum_quantity) = self.minimum_quantity {
+            conditions.push(Condition::ProductMinimumQuantity(ProductMinimumQuantity {
+                ids: ids.clone(),
+                minimum_quantity,
+                target_type: TargetType::ProductVariant,
+            }));
+        }
+        if let Some(minimum_amount) = self.minimum_subtotal {
+            conditions.push(Condition::ProductMinimumSubtotal(ProductMinimumSubtotal {
+                ids,
+                minimum_amount,
+                target_type: TargetType::ProductVariant,
+            }));
+        }
+        (!conditions.is_empty()).then_some(conditions)
     }
 }
 
@@ -22,64 +242,183 @@ impl Default for Configuration {
     fn default() -> Self {
         Configuration {
             value: Self::DEFAULT_VALUE,
+            discount_type: DiscountType::Percentage,
+            applies_to_each_item: false,
             excluded_variant_ids: vec![],
+            quantity_limit: None,
+            minimum_quantity: None,
+            minimum_subtotal: None,
+            products: ProductRules::default(),
+            exclude_sale_items: false,
+            maximum_markdown: None,
+            currencies: HashMap::new(),
+            rounding: Rounding::default(),
+            messages: Messages::default(),
         }
     }
 }
 
-impl input::Input {
-    pub fn configuration(&self) -> Configuration {
-        match &self.discount_node.metafield {
-            Some(input::Metafield { value }) => Configuration::from_str(value),
-            None => Configuration::default(),
+impl config::Validate for Configuration {
+    fn validate(&self) -> Result<(), config::ConfigurationError> {
+        match self.discount_type {
+            DiscountType::Percentage => config::validate_percentage("value", self.value)?,
+            DiscountType::FixedAmount => config::validate_non_negative("value", self.value)?,
+        }
+        if let Some(minimum_quantity) = self.minimum_quantity {
+            config::validate_non_negative("minimumQuantity", minimum_quantity.into())?;
+        }
+        if let Some(minimum_subtotal) = self.minimum_subtotal {
+            config::validate_non_negative("minimumSubtotal", minimum_subtotal)?;
+        }
+        if let Some(maximum_markdown) = self.maximum_markdown {
+            config::validate_percentage("maximumMarkdown", maximum_markdown)?;
+        }
+        for overrides in self.currencies.values() {
+            if let Some(value) = overrides.value {
+                config::validate_non_negative("currencies.value", value)?;
+            }
+            if let Some(minimum_subtotal) = overrides.minimum_subtotal {
+                config::validate_non_negative("currencies.minimumSubtotal", minimum_subtotal)?;
+            }
+        }
+        config::Validate::validate(&self.messages)?;
+        match self.quantity_limit {
+            Some(QuantityLimit::PerVariant(quantity) | QuantityLimit::PerOrder(quantity))
+                if quantity <= 0 =>
+            {
+                Err(config::ConfigurationError::OutOfRange {
+                    field: "quantityLimit",
+                    value: quantity.into(),
+                    expected: "a quantity greater than 0",
+                })
+            }
+            _ => Ok(()),
         }
     }
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let input: input::Input = serde_json::from_reader(std::io::BufReader::new(std::io::stdin()))?;
-    let mut out = std::io::stdout();
-    let mut serializer = serde_json::Serializer::new(&mut out);
-    function(input)?.serialize(&mut serializer)?;
-    Ok(())
+impl input::Input {
+    pub fn configuration(&self) -> Result<Configuration, config::ConfigurationError> {
+        let value = self
+            .discount_node
+            .metafield
+            .as_ref()
+            .map(|metafield| metafield.value.as_str());
+        config::load(value, config::Mode::FailOpen)
+    }
+
+    fn presentment(&self) -> Presentment {
+        Presentment::new(
+            self.cart.cost.subtotal_amount.currency_code,
+            self.presentment_currency_rate,
+        )
+    }
+}
+
+fn main() -> std::process::ExitCode {
+    shopify_function_core::run(function)
 }
 
-fn function(input: input::Input) -> Result<FunctionResult, Box<dyn std::error::Error>> {
-    let config = input.configuration();
-    let targets = targets(&input.cart.lines, &config.excluded_variant_ids);
-    Ok(build_result(config.value, targets))
+pub(crate) fn function(input: input::Input) -> Result<FunctionResult, Box<dyn std::error::Error>> {
+    let config = input.configuration()?.in_currency(&input.presentment())?;
+    let currency_code = input.cart.cost.subtotal_amount.currency_code;
+    let targets = targets(&input.cart.lines, &config);
+    let conditions = config.conditions(&targets);
+    let values = match config.maximum_markdown {
+        Some(maximum_markdown) => markdown::cap(
+            config.discount_value()?,
+            targets,
+            &input.cart.lines,
+            maximum_markdown,
+            currency_code,
+        )?,
+        None => vec![(config.discount_value()?, targets)],
+    };
+    let discounts = values
+        .into_iter()
+        .filter(|(_, targets)| !targets.is_empty())
+        .map(|(value, targets)| Discount {
+            message: config.message(&value, input.localization.language.iso_code, currency_code),
+            conditions: conditions.clone(),
+            targets,
+            value,
+        })
+        .collect();
+    Ok(build_result(discounts))
 }
 
-fn targets(cart_lines: &[input::CartLine], excluded_variant_ids: &[ID]) -> Vec<Target> {
-    cart_lines
+fn targets(cart_lines: &[input::CartLine], config: &Configuration) -> Vec<Target> {
+    let eligible_lines = cart_lines
         .iter()
+        .filter(|line| !config.exclude_sale_items || !is_on_sale(&line.cost))
         .filter_map(|line| match &line.merchandise.id {
-            Some(id) => {
-                if !excluded_variant_ids.contains(id) {
-                    Some(Target::ProductVariant {
-                        id: id.to_string(),
-                        quantity: None,
-                    })
-                } else {
-                    None
-                }
+            Some(id)
+                if !config.excluded_variant_ids.contains(id)
+                    && config
+                        .products
+                        .applies_to(line.merchandise.product.as_ref()) =>
+            {
+                Some((id, line.quantity))
             }
-            None => None,
+            _ => None,
+        });
+    match config.quantity_limit {
+        Some(quantity_limit) => limited_targets(eligible_lines, quantity_limit),
+        None => eligible_lines
+            .map(|(id, _)| Target::ProductVariant {
+                id: id.to_string(),
+                quantity: None,
+            })
+            .collect(),
+    }
+}
+
+fn is_on_sale(cost: &input::CartLineCost) -> bool {
+    cost.compare_at_amount
+        .as_ref()
+        .is_some_and(|compare_at_amount| compare_at_amount.amount > cost.amount.amount)
+}
+
+fn limited_targets<'a>(
+    lines: impl Iterator<Item = (&'a ID, Int)>,
+    quantity_limit: QuantityLimit,
+) -> Vec<Target> {
+    // A target's quantity covers every line of its variant, so lines are
+    // grouped by variant before the limit is applied. Lines with no units
+    // don't count against it.
+    let mut quantities: Vec<(&ID, Int)> = vec![];
+    for (id, quantity) in lines {
+        let quantity = quantity.max(0);
+        match quantities.iter_mut().find(|(other, _)| *other == id) {
+            Some((_, total)) => *total = total.saturating_add(quantity),
+            None => quantities.push((id, quantity)),
+        }
+    }
+
+    let mut remaining = match quantity_limit {
+        QuantityLimit::PerVariant(_) => Int::MAX,
+        QuantityLimit::PerOrder(limit) => limit,
+    };
+    quantities
+        .into_iter()
+        .filter_map(|(id, quantity)| {
+            let quantity = match quantity_limit {
+                QuantityLimit::PerVariant(limit) => quantity.min(limit),
+                QuantityLimit::PerOrder(_) => {
+                    let quantity = quantity.min(remaining);
+                    remaining = remaining.saturating_sub(quantity);
+                    quantity
+                }
+            };
+            (quantity > 0).then(|| Target::ProductVariant {
+                id: id.to_string(),
+                quantity: Some(quantity),
+            })
         })
         .collect()
 }
 
-fn build_result(value: f64, targets: Vec<Target>) -> FunctionResult {
-    let discounts = if targets.is_empty() {
-        vec![]
-    } else {
-        vec![Discount {
-            message: None,
-            conditions: None,
-            targets,
-            value: Value::Percentage(Percentage { value }),
-        }]
-    };
+fn build_result(discounts: Vec<Discount>) -> FunctionResult {
     FunctionResult {
         discounts,
         discount_application_strategy: DiscountApplicationStrategy::First,
@@ -89,6 +428,22 @@ fn build_result(value: f64, targets: Vec<Target>) -> FunctionResult {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use shopify_function_core::fixtures;
+    use shopify_function_core::validate;
+
+    fn function(input: input::Input) -> Result<FunctionResult, Box<dyn std::error::Error>> {
+        validate::checked(include_str!("../schema.graphql"), super::function, input)
+    }
+
+    fn cost(amount: &str, compare_at_amount: Option<&str>) -> input::CartLineCost {
+        let money = |amount: &str| input::CartLineCostAmount {
+            amount: amount.parse().unwrap(),
+        };
+        input::CartLineCost {
+            amount: money(amount),
+            compare_at_amount: compare_at_amount.map(money),
+        }
+    }
 
     fn input(configuration: Option<Configuration>) -> input::Input {
         let discount_node = input::DiscountNode {
@@ -99,21 +454,38 @@ mod tests {
         };
         input::Input {
             cart: input::Cart {
+                cost: input::CartCost {
+                    subtotal_amount: input::MoneyV2 {
+                        currency_code: CurrencyCode::Usd,
+                    },
+                },
                 lines: vec![
                     input::CartLine {
                         id: String::from("gid://shopify/CartLine/0"),
+                        quantity: 3,
+                        cost: cost("10.00", None),
                         merchandise: input::Merchandise {
                             id: Some(String::from("gid://shopify/ProductVariant/0")),
+                            product: None,
                         },
                     },
                     input::CartLine {
                         id: String::from("gid://shopify/CartLine/1"),
+                        quantity: 1,
+                        cost: cost("10.00", None),
                         merchandise: input::Merchandise {
                             id: Some(String::from("gid://shopify/ProductVariant/1")),
+                            product: None,
                         },
                     },
                 ],
             },
+            presentment_currency_rate: None,
+            localization: input::Localization {
+                language: input::Language {
+                    iso_code: LanguageCode::En,
+                },
+            },
             discount_node,
         }
     }
@@ -140,7 +512,7 @@ mod tests {
     fn test_discount_with_value() {
         let input = input(Some(Configuration {
             value: 10.0,
-            excluded_variant_ids: vec![],
+            ..Configuration::default()
         }));
         let result = serde_json::json!(function(input).unwrap());
 
@@ -160,8 +532,8 @@ mod tests {
     #[test]
     fn test_discount_with_excluded_variant_ids() {
         let input = input(Some(Configuration {
-            value: Configuration::DEFAULT_VALUE,
             excluded_variant_ids: vec!["gid://shopify/ProductVariant/1".to_string()],
+            ..Configuration::default()
         }));
         let result = serde_json::json!(function(input).unwrap());
 
@@ -180,7 +552,10 @@ mod tests {
     #[test]
     fn test_discount_with_no_cart_lines() {
         let input = input::Input {
-            cart: input::Cart { lines: vec![] },
+            cart: input::Cart {
+                lines: vec![],
+                ..input(None).cart
+            },
             ..input(Some(Configuration::default()))
         };
         let handle_result = serde_json::json!(function(input).unwrap());
@@ -198,8 +573,14 @@ mod tests {
             cart: input::Cart {
                 lines: vec![input::CartLine {
                     id: "gid://shopify/CartLine/0".to_string(),
-                    merchandise: input::Merchandise { id: None },
+                    quantity: 1,
+                    cost: cost("10.00", None),
+                    merchandise: input::Merchandise {
+                        id: None,
+                        product: None,
+                    },
                 }],
+                ..input(None).cart
             },
             ..input(Some(Configuration::default()))
         };
@@ -211,4 +592,766 @@ mod tests {
         });
         assert_eq!(handle_result, expected_handle_result);
     }
+
+    #[test]
+    fn test_discount_with_invalid_configuration() {
+        let input = input::Input {
+            discount_node: input::DiscountNode {
+                metafield: Some(input::Metafield {
+                    value: r#"{"value":"10"#.to_string(),
+                }),
+            },
+            ..input(None)
+        };
+        let result = serde_json::json!(function(input).unwrap());
+
+        let expected_result = serde_json::json!({
+            "discounts": [{
+                "targets": [
+                    { "productVariant": { "id": "gid://shopify/ProductVariant/0" } },
+                    { "productVariant": { "id": "gid://shopify/ProductVariant/1" } },
+                ],
+                "value": { "percentage": { "value": 50.0 } },
+            }],
+            "discountApplicationStrategy": "FIRST",
+        });
+        assert_eq!(result, expected_result);
+    }
+
+    #[test]
+    fn test_discount_with_fixed_amount() {
+        let input = input(Some(Configuration {
+            value: 5.0,
+            discount_type: DiscountType::FixedAmount,
+            ..Configuration::default()
+        }));
+        let result = serde_json::json!(function(input).unwrap());
+
+        let expected_result = serde_json::json!({
+            "discounts": [{
+                "targets": [
+                    { "productVariant": { "id": "gid://shopify/ProductVariant/0" } },
+                    { "productVariant": { "id": "gid://shopify/ProductVariant/1" } },
+                ],
+                "value": { "fixedAmount": { "amount": "5", "appliesToEachItem": false } },
+            }],
+            "discountApplicationStrategy": "FIRST",
+        });
+        assert_eq!(result, expected_result);
+    }
+
+    #[test]
+    fn test_discount_with_fixed_amount_applied_to_each_item() {
+        let input = input::Input {
+            discount_node: input::DiscountNode {
+                metafield: Some(input::Metafield {
+                    value: r#"{"value":5.5,"discountType":"fixedAmount","appliesToEachItem":true,"excludedVariantIds":[]}"#.to_string(),
+                }),
+            },
+            ..input(None)
+        };
+        let result = serde_json::json!(function(input).unwrap());
+
+        let expected_result = serde_json::json!({
+            "discounts": [{
+                "targets": [
+                    { "productVariant": { "id": "gid://shopify/ProductVariant/0" } },
+                    { "productVariant": { "id": "gid://shopify/ProductVariant/1" } },
+                ],
+                "value": { "fixedAmount": { "amount": "5.5", "appliesToEachItem": true } },
+            }],
+            "discountApplicationStrategy": "FIRST",
+        });
+        assert_eq!(result, expected_result);
+    }
+
+    #[test]
+    fn test_fixed_amount_above_one_hundred_is_valid() {
+        let configuration = Configuration {
+            value: 150.0,
+            discount_type: DiscountType::FixedAmount,
+            ..Configuration::default()
+        };
+
+        assert!(config::Validate::validate(&configuration).is_ok());
+        assert!(config::Validate::validate(&Configuration {
+            value: -5.0,
+            ..configuration
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_discount_with_quantity_limit_per_variant() {
+        let input = input(Some(Configuration {
+            quantity_limit: Some(QuantityLimit::PerVariant(2)),
+            ..Configuration::default()
+        }));
+        let result = serde_json::json!(function(input).unwrap());
+
+        let expected_result = serde_json::json!({
+            "discounts": [{
+                "targets": [
+                    { "productVariant": { "id": "gid://shopify/ProductVariant/0", "quantity": 2 } },
+                    { "productVariant": { "id": "gid://shopify/ProductVariant/1", "quantity": 1 } },
+                ],
+                "value": { "percentage": { "value": 50.0 } },
+            }],
+            "discountApplicationStrategy": "FIRST",
+        });
+        assert_eq!(result, expected_result);
+    }
+
+    #[test]
+    fn test_discount_with_quantity_limit_per_order() {
+        let input = input(Some(Configuration {
+            quantity_limit: Some(QuantityLimit::PerOrder(3)),
+            ..Configuration::default()
+        }));
+        let result = serde_json::json!(function(input).unwrap());
+
+        let expected_result = serde_json::json!({
+            "discounts": [{
+                "targets": [
+                    { "productVariant": { "id": "gid://shopify/ProductVariant/0", "quantity": 3 } },
+                ],
+                "value": { "percentage": { "value": 50.0 } },
+            }],
+            "discountApplicationStrategy": "FIRST",
+        });
+        assert_eq!(result, expected_result);
+    }
+
+    #[test]
+    fn test_quantity_limit_groups_lines_of_the_same_variant() {
+        let variant_line = |id: &str, quantity| input::CartLine {
+            id: id.to_string(),
+            quantity,
+            cost: cost("10.00", None),
+            merchandise: input::Merchandise {
+                id: Some("gid://shopify/ProductVariant/0".to_string()),
+                product: None,
+            },
+        };
+        let cart_lines = vec![
+            variant_line("gid://shopify/CartLine/0", 1),
+            variant_line("gid://shopify/CartLine/1", 1),
+        ];
+
+        assert_eq!(
+            targets(
+                &cart_lines,
+                &Configuration {
+                    quantity_limit: Some(QuantityLimit::PerVariant(5)),
+                    ..Configuration::default()
+                }
+            ),
+            vec![Target::ProductVariant {
+                id: "gid://shopify/ProductVariant/0".to_string(),
+                quantity: Some(2),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_quantity_limit_ignores_lines_without_units() {
+        let variant_line = |id: &str, variant: &str, quantity| input::CartLine {
+            id: id.to_string(),
+            quantity,
+            cost: cost("10.00", None),
+            merchandise: input::Merchandise {
+                id: Some(variant.to_string()),
+                product: None,
+            },
+        };
+        let cart_lines = vec![
+            variant_line(
+                "gid://shopify/CartLine/0",
+                "gid://shopify/ProductVariant/0",
+                -2,
+            ),
+            variant_line(
+                "gid://shopify/CartLine/1",
+                "gid://shopify/ProductVariant/1",
+                0,
+            ),
+            variant_line(
+                "gid://shopify/CartLine/2",
+                "gid://shopify/ProductVariant/1",
+                3,
+            ),
+            variant_line(
+                "gid://shopify/CartLine/3",
+                "gid://shopify/ProductVariant/2",
+                2,
+            ),
+        ];
+        let targets_with = |quantity_limit| {
+            targets(
+                &cart_lines,
+                &Configuration {
+                    quantity_limit: Some(quantity_limit),
+                    ..Configuration::default()
+                },
+            )
+        };
+        let target = |id: &str, quantity| Target::ProductVariant {
+            id: id.to_string(),
+            quantity: Some(quantity),
+        };
+
+        assert_eq!(
+            targets_with(QuantityLimit::PerVariant(2)),
+            vec![
+                target("gid://shopify/ProductVariant/1", 2),
+                target("gid://shopify/ProductVariant/2", 2),
+            ]
+        );
+        assert_eq!(
+            targets_with(QuantityLimit::PerOrder(4)),
+            vec![
+                target("gid://shopify/ProductVariant/1", 3),
+                target("gid://shopify/ProductVariant/2", 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_quantity_limit_must_be_positive() {
+        let configuration = Configuration {
+            quantity_limit: Some(QuantityLimit::PerOrder(0)),
+            ..Configuration::default()
+        };
+
+        assert!(config::Validate::validate(&configuration).is_err());
+    }
+
+    #[test]
+    fn test_discount_with_minimum_quantity_and_subtotal() {
+        let input = input(Some(Configuration {
+            value: 10.0,
+            excluded_variant_ids: vec!["gid://shopify/ProductVariant/1".to_string()],
+            minimum_quantity: Some(3),
+            minimum_subtotal: Some(25.0),
+            ..Configuration::default()
+        }));
+        let result = serde_json::json!(function(input).unwrap());
+
+        let expected_result = serde_json::json!({
+            "discounts": [{
+                "targets": [
+                    { "productVariant": { "id": "gid://shopify/ProductVariant/0" } },
+                ],
+                "value": { "percentage": { "value": 10.0 } },
+                "conditions": [
+                    {
+                        "productMinimumQuantity": {
+                            "ids": ["gid://shopify/ProductVariant/0"],
+                            "minimumQuantity": 3,
+                            "targetType": "PRODUCT_VARIANT",
+                        }
+                    },
+                    {
+                        "productMinimumSubtotal": {
+                            "ids": ["gid://shopify/ProductVariant/0"],
+                            "minimumAmount": 25.0,
+                            "targetType": "PRODUCT_VARIANT",
+                        }
+                    },
+                ],
+            }],
+            "discountApplicationStrategy": "FIRST",
+        });
+        assert_eq!(result, expected_result);
+    }
+
+    /// The discount on a cart in `currency_code`, at `rate` from the shop's
+    /// currency.
+    fn discount_in_currency(
+        configuration: &str,
+        currency_code: CurrencyCode,
+        rate: Option<&str>,
+    ) -> serde_json::Value {
+        let input = input::Input {
+            cart: input::Cart {
+                cost: input::CartCost {
+                    subtotal_amount: input::MoneyV2 { currency_code },
+                },
+                ..input(None).cart
+            },
+            presentment_currency_rate: rate.map(|rate| rate.parse().unwrap()),
+            discount_node: input::DiscountNode {
+                metafield: Some(input::Metafield {
+                    value: configuration.to_string(),
+                }),
+            },
+            ..input(None)
+        };
+        serde_json::json!(function(input).unwrap().discounts[0])
+    }
+
+    #[test]
+    fn test_fixed_amount_in_presentment_currency() {
+        let configuration = r#"{
+            "value": 10,
+            "discountType": "fixedAmount",
+            "excludedVariantIds": [],
+            "minimumSubtotal": 50,
+            "currencies": { "JPY": { "value": 1500 } },
+            "rounding": "ninetyNine"
+        }"#;
+        let amounts = |currency_code, rate| {
+            let discount = discount_in_currency(configuration, currency_code, rate);
+            (
+                discount["value"]["fixedAmount"]["amount"].clone(),
+                discount["conditions"][0]["productMinimumSubtotal"]["minimumAmount"].clone(),
+            )
+        };
+
+        assert_eq!(
+            amounts(CurrencyCode::Usd, None),
+            (serde_json::json!("10"), serde_json::json!(50.0))
+        );
+        assert_eq!(
+            amounts(CurrencyCode::Cad, Some("1.3456")),
+            (serde_json::json!("13.99"), serde_json::json!(67.99))
+        );
+        assert_eq!(
+            amounts(CurrencyCode::Eur, Some("0.92")),
+            (serde_json::json!("9.99"), serde_json::json!(46.99))
+        );
+        assert_eq!(
+            amounts(CurrencyCode::Jpy, Some("134.5")),
+            (serde_json::json!("1500"), serde_json::json!(6725.0))
+        );
+    }
+
+    #[test]
+    fn test_percentage_is_not_converted() {
+        let configuration = r#"{
+            "value": 10,
+            "excludedVariantIds": [],
+            "currencies": { "EUR": { "value": 20 } }
+        }"#;
+        let discount = discount_in_currency(configuration, CurrencyCode::Eur, Some("0.92"));
+
+        assert_eq!(
+            discount["value"],
+            serde_json::json!({ "percentage": { "value": 10.0 } })
+        );
+    }
+
+    fn product(vendor: &str, product_type: &str) -> input::Product {
+        input::Product {
+            in_included_collection: false,
+            in_excluded_collection: false,
+            has_included_tag: false,
+            has_excluded_tag: false,
+            vendor: Some(vendor.to_string()),
+            product_type: Some(product_type.to_string()),
+            is_gift_card: false,
+        }
+    }
+
+    fn product_line(n: usize, product: Option<input::Product>) -> input::CartLine {
+        input::CartLine {
+            id: format!("gid://shopify/CartLine/{}", n),
+            quantity: 1,
+            cost: cost("10.00", None),
+            merchandise: input::Merchandise {
+                id: Some(format!("gid://shopify/ProductVariant/{}", n)),
+                product,
+            },
+        }
+    }
+
+    fn variant_ids(cart_lines: &[input::CartLine], config: &Configuration) -> Vec<ID> {
+        targets(cart_lines, config)
+            .into_iter()
+            .map(|Target::ProductVariant { id, .. }| id)
+            .collect()
+    }
+
+    #[test]
+    fn test_discount_for_included_and_excluded_products() {
+        let cart_lines = vec![
+            product_line(0, Some(product("Acme", "Shirt"))),
+            product_line(1, Some(product("Acme", "Hat"))),
+            product_line(2, Some(product("Other", "Shirt"))),
+            product_line(
+                3,
+                Some(input::Product {
+                    in_included_collection: true,
+                    ..product("Other", "Shirt")
+                }),
+            ),
+            product_line(
+                4,
+                Some(input::Product {
+                    has_excluded_tag: true,
+                    ..product("Acme", "Shirt")
+                }),
+            ),
+        ];
+        let config: Configuration = serde_json::from_str(
+            r#"{
+                "value": 10,
+                "excludedVariantIds": [],
+                "includedCollectionIds": ["gid://shopify/Collection/0"],
+                "includedVendors": ["Acme"],
+                "excludedTags": ["final-sale"],
+                "excludedProductTypes": ["Hat"]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            variant_ids(&cart_lines, &config),
+            vec![
+                "gid://shopify/ProductVariant/0",
+                "gid://shopify/ProductVariant/3"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_discount_for_gift_cards() {
+        let cart_lines = vec![
+            product_line(
+                0,
+                Some(input::Product {
+                    is_gift_card: true,
+                    ..product("Acme", "Gift card")
+                }),
+            ),
+            product_line(1, Some(product("Acme", "Shirt"))),
+            product_line(2, None),
+        ];
+        let variant_ids = |gift_cards| {
+            let config = Configuration {
+                products: ProductRules {
+                    gift_cards,
+                    ..ProductRules::default()
+                },
+                ..Configuration::default()
+            };
+            variant_ids(&cart_lines, &config)
+        };
+
+        assert_eq!(variant_ids(GiftCards::Included).len(), 3);
+        assert_eq!(
+            variant_ids(GiftCards::Excluded),
+            vec![
+                "gid://shopify/ProductVariant/1",
+                "gid://shopify/ProductVariant/2"
+            ]
+        );
+        assert_eq!(
+            variant_ids(GiftCards::Only),
+            vec!["gid://shopify/ProductVariant/0"]
+        );
+    }
+
+    #[test]
+    fn test_product_rules_without_lists_are_ignored() {
+        // The query may answer for the lists of a rejected configuration.
+        let cart_lines = vec![product_line(
+            0,
+            Some(input::Product {
+                in_excluded_collection: true,
+                has_excluded_tag: true,
+                ..product("Acme", "Shirt")
+            }),
+        )];
+
+        assert_eq!(
+            variant_ids(&cart_lines, &Configuration::default()),
+            vec!["gid://shopify/ProductVariant/0"]
+        );
+    }
+
+    #[test]
+    fn test_message_in_buyer_language() {
+        let messages = r#"{
+            "templates": {
+                "EN": "{amount} {currency} off",
+                "FR": "{amount} {currency} de réduction",
+                "DE": "{percent} % Rabatt"
+            },
+            "fallbackLanguage": "EN"
+        }"#;
+        let message = |discount_type, iso_code, currency_code| {
+            let input = input::Input {
+                cart: input::Cart {
+                    cost: input::CartCost {
+                        subtotal_amount: input::MoneyV2 { currency_code },
+                    },
+                    ..input(None).cart
+                },
+                localization: input::Localization {
+                    language: input::Language { iso_code },
+                },
+                ..input(Some(Configuration {
+                    value: 5.0,
+                    discount_type,
+                    messages: serde_json::from_str(messages).unwrap(),
+                    ..Configuration::default()
+                }))
+            };
+            function(input).unwrap().discounts[0].message.clone()
+        };
+
+        assert_eq!(
+            message(
+                DiscountType::FixedAmount,
+                LanguageCode::Fr,
+                CurrencyCode::Usd
+            )
+            .unwrap(),
+            "5.00 USD de réduction"
+        );
+        assert_eq!(
+            message(
+                DiscountType::FixedAmount,
+                LanguageCode::Es,
+                CurrencyCode::Jpy
+            )
+            .unwrap(),
+            "5 JPY off"
+        );
+        assert_eq!(
+            message(
+                DiscountType::Percentage,
+                LanguageCode::De,
+                CurrencyCode::Usd
+            )
+            .unwrap(),
+            "5 % Rabatt"
+        );
+    }
+
+    #[test]
+    fn test_messages_need_a_fallback_template() {
+        let configuration = Configuration {
+            messages: serde_json::from_str(
+                r#"{ "templates": { "FR": "{percent} % de réduction" }, "fallbackLanguage": "EN" }"#,
+            )
+            .unwrap(),
+            ..Configuration::default()
+        };
+
+        assert_eq!(
+            config::Validate::validate(&configuration)
+                .unwrap_err()
+                .to_string(),
+            "configuration `messages.fallbackLanguage` is EN, expected a language with a template"
+        );
+    }
+
+    fn priced_line(
+        n: usize,
+        quantity: Int,
+        amount: &str,
+        compare_at_amount: Option<&str>,
+    ) -> input::CartLine {
+        input::CartLine {
+            quantity,
+            cost: cost(amount, compare_at_amount),
+            ..product_line(n, None)
+        }
+    }
+
+    /// The discounts on `lines`, as JSON.
+    fn discounts_on(
+        lines: Vec<input::CartLine>,
+        configuration: Configuration,
+    ) -> serde_json::Value {
+        let input = input::Input {
+            cart: input::Cart {
+                lines,
+                ..input(None).cart
+            },
+            ..input(Some(configuration))
+        };
+        serde_json::json!(function(input).unwrap().discounts)
+    }
+
+    #[test]
+    fn test_sale_items_are_excluded() {
+        let cart_lines = vec![
+            priced_line(0, 1, "80.00", Some("100.00")),
+            priced_line(1, 1, "50.00", Some("50.00")),
+            priced_line(2, 1, "50.00", None),
+        ];
+        let config = Configuration {
+            exclude_sale_items: true,
+            ..Configuration::default()
+        };
+
+        assert_eq!(
+            variant_ids(&cart_lines, &config),
+            vec![
+                "gid://shopify/ProductVariant/1",
+                "gid://shopify/ProductVariant/2"
+            ]
+        );
+        assert_eq!(variant_ids(&cart_lines, &Configuration::default()).len(), 3);
+    }
+
+    #[test]
+    fn test_maximum_markdown_lowers_a_percentage() {
+        let config = Configuration {
+            value: 20.0,
+            maximum_markdown: Some(30.0),
+            ..Configuration::default()
+        };
+        // 20% off the sale item would mark it down by 36%, it can take 12.5%.
+        let discounts = discounts_on(
+            vec![
+                priced_line(0, 1, "80.00", Some("100.00")),
+                priced_line(1, 1, "50.00", None),
+            ],
+            config,
+        );
+
+        assert_eq!(
+            discounts,
+            serde_json::json!([
+                {
+                    "targets": [
+                        { "productVariant": { "id": "gid://shopify/ProductVariant/1" } },
+                    ],
+                    "value": { "percentage": { "value": 20.0 } },
+                },
+                {
+                    "targets": [
+                        { "productVariant": { "id": "gid://shopify/ProductVariant/0" } },
+                    ],
+                    "value": { "percentage": { "value": 12.5 } },
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_maximum_markdown_keeps_the_value_of_other_lines() {
+        let config = Configuration {
+            value: 20.0,
+            maximum_markdown: Some(25.0),
+            ..Configuration::default()
+        };
+        // The expensive line is most of the total, but only it can't take 20%.
+        let discounts = discounts_on(
+            vec![
+                priced_line(0, 1, "1000.00", Some("1100.00")),
+                priced_line(1, 3, "10.00", None),
+                priced_line(2, 1, "25.00", None),
+            ],
+            config,
+        );
+
+        assert_eq!(
+            discounts[0],
+            serde_json::json!({
+                "targets": [
+                    { "productVariant": { "id": "gid://shopify/ProductVariant/1" } },
+                    { "productVariant": { "id": "gid://shopify/ProductVariant/2" } },
+                ],
+                "value": { "percentage": { "value": 20.0 } },
+            })
+        );
+        assert_eq!(
+            discounts[1]["value"],
+            serde_json::json!({ "percentage": { "value": 17.5 } })
+        );
+    }
+
+    #[test]
+    fn test_maximum_markdown_lowers_a_fixed_amount() {
+        let config = Configuration {
+            value: 10.0,
+            discount_type: DiscountType::FixedAmount,
+            applies_to_each_item: true,
+            maximum_markdown: Some(25.0),
+            ..Configuration::default()
+        };
+        let discounts = discounts_on(
+            vec![
+                priced_line(0, 3, "80.00", Some("100.00")),
+                priced_line(1, 1, "50.00", None),
+            ],
+            config.clone(),
+        );
+
+        assert_eq!(
+            discounts,
+            serde_json::json!([
+                {
+                    "targets": [
+                        { "productVariant": { "id": "gid://shopify/ProductVariant/1" } },
+                    ],
+                    "value": { "fixedAmount": { "amount": "10", "appliesToEachItem": true } },
+                },
+                {
+                    "targets": [
+                        { "productVariant": { "id": "gid://shopify/ProductVariant/0" } },
+                    ],
+                    "value": { "fixedAmount": { "amount": "5.00", "appliesToEachItem": true } },
+                },
+            ])
+        );
+
+        // Items marked down by more than the maximum aren't discounted, and the
+        // amount spread across the others keeps their part of it.
+        let discounts = discounts_on(
+            vec![
+                priced_line(0, 1, "50.00", Some("100.00")),
+                priced_line(1, 1, "50.00", None),
+            ],
+            Configuration {
+                applies_to_each_item: false,
+                ..config
+            },
+        );
+
+        assert_eq!(
+            discounts,
+            serde_json::json!([{
+                "targets": [
+                    { "productVariant": { "id": "gid://shopify/ProductVariant/1" } },
+                ],
+                "value": { "fixedAmount": { "amount": "5.00", "appliesToEachItem": false } },
+            }])
+        );
+    }
+
+    #[test]
+    fn test_maximum_markdown_sums_the_units_of_a_variant() {
+        let config = Configuration {
+            value: 10.0,
+            discount_type: DiscountType::FixedAmount,
+            maximum_markdown: Some(25.0),
+            ..Configuration::default()
+        };
+        // The units add up past `Int::MAX`, and the line without units
+        // doesn't price the variant.
+        let discounts = discounts_on(
+            vec![
+                priced_line(0, Int::MAX, "1.00", None),
+                priced_line(0, Int::MAX, "1.00", None),
+                priced_line(0, -1, "1.00", Some("100.00")),
+            ],
+            config,
+        );
+
+        assert_eq!(
+            discounts[0]["value"],
+            serde_json::json!({ "fixedAmount": { "amount": "10", "appliesToEachItem": false } })
+        );
+    }
+
+    #[test]
+    fn test_fixtures() {
+        fixtures::check("tests/fixtures", function);
+    }
 }
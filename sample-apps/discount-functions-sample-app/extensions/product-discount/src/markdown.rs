@@ -0,0 +1,218 @@
+//! Capping the total markdown of the discounted items, compare-at price and
+//! discount together.
+//!
+//! A discount has one value for all of its targets, so the targets that can
+//! take the whole value keep it in the first discount, and the others get
+//! smaller values in discounts of their own, grouped by value. Since only the
+//! first discount of a result applies, those are only used when no target can
+//! take the whole value.
+
+use std::cmp::Reverse;
+
+use shopify_function_core::money::MoneyError;
+use shopify_function_core::product_discounts::*;
+use shopify_function_core::CurrencyCode;
+
+use super::input;
+
+/// A target, with the price of one of its items.
+struct Item {
+    target: Target,
+    price: Decimal,
+    quantity: Decimal,
+    /// The part of the price the discount can take off, between 0 and 1.
+    allowance: Decimal,
+}
+
+impl Item {
+    fn subtotal(&self) -> Option<Decimal> {
+        self.price.checked_mul(self.quantity)
+    }
+}
+
+/// How a discount value takes money off its targets.
+enum Kind {
+    /// A percentage of each target's price.
+    Percentage(Decimal),
+    /// An amount off each item.
+    PerItem(Decimal),
+    /// An amount spread across the targets by price, which takes the same part
+    /// off each of them.
+    Spread(Decimal),
+}
+
+impl Kind {
+    fn new(value: &Value) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(match value {
+            Value::Percentage(Percentage { value }) => Kind::Percentage(Decimal::try_from(*value)?),
+            Value::FixedAmount(FixedAmount {
+                amount,
+                applies_to_each_item: Some(true),
+            }) => Kind::PerItem(*amount),
+            Value::FixedAmount(FixedAmount { amount, .. }) => Kind::Spread(*amount),
+        })
+    }
+
+    /// The most an item can take, in the unit of `ceiling`.
+    fn limit(&self, item: &Item) -> Decimal {
+        match self {
+            Kind::Percentage(_) => item.allowance * Decimal::ONE_HUNDRED,
+            Kind::PerItem(_) => item.allowance * item.price,
+            Kind::Spread(_) => item.allowance,
+        }
+    }
+
+    /// The value every item is discounted by: a percentage, an amount per item
+    /// or the part of the price.
+    fn ceiling(&self, subtotal: Decimal) -> Option<Decimal> {
+        match self {
+            Kind::Percentage(value) | Kind::PerItem(value) => Some(*value),
+            Kind::Spread(amount) => amount.checked_div(subtotal),
+        }
+    }
+
+    /// The `limit` rounded down to what the discount value can hold.
+    fn rounded(&self, limit: Decimal, currency_code: CurrencyCode) -> Decimal {
+        match self {
+            Kind::Percentage(_) => limit.trunc_with_scale(2),
+            Kind::PerItem(_) => limit.trunc_with_scale(currency_code.minor_units()),
+            Kind::Spread(_) => limit,
+        }
+    }
+
+    /// The discount value taking `limit` off each of `items`.
+    fn value(
+        &self,
+        limit: Decimal,
+        items: &[Item],
+        currency_code: CurrencyCode,
+    ) -> Result<Value, Box<dyn std::error::Error>> {
+        Ok(match self {
+            Kind::Percentage(_) => Value::Percentage(Percentage {
+                value: f64::try_from(self.rounded(limit, currency_code))?,
+            }),
+            Kind::PerItem(_) => Value::FixedAmount(FixedAmount {
+                amount: self.rounded(limit, currency_code),
+                applies_to_each_item: Some(true),
+            }),
+            Kind::Spread(_) => {
+                let subtotal = subtotal(items).ok_or(MoneyError::Overflow)?;
+                let amount = limit.checked_mul(subtotal).ok_or(MoneyError::Overflow)?;
+                Value::FixedAmount(FixedAmount {
+                    amount: amount.trunc_with_scale(currency_code.minor_units()),
+                    applies_to_each_item: Some(false),
+                })
+            }
+        })
+    }
+}
+
+/// Whether the `value` takes nothing off.
+fn is_zero(value: &Value) -> bool {
+    match value {
+        Value::Percentage(Percentage { value }) => *value <= 0.0,
+        Value::FixedAmount(FixedAmount { amount, .. }) => *amount <= Decimal::ZERO,
+    }
+}
+
+fn subtotal(items: &[Item]) -> Option<Decimal> {
+    items.iter().try_fold(Decimal::ZERO, |subtotal, item| {
+        subtotal.checked_add(item.subtotal()?)
+    })
+}
+
+/// The part of a line's price a discount can take off without the line being
+/// marked down by more than `maximum_markdown` (between 0 and 1) of its
+/// compare-at price. Lines without one are only marked down by the discount.
+fn allowance(cost: &input::CartLineCost, maximum_markdown: Decimal) -> Decimal {
+    let price = cost.amount.amount;
+    if price <= Decimal::ZERO {
+        return Decimal::ZERO;
+    }
+    let compare_at_price = cost
+        .compare_at_amount
+        .as_ref()
+        .map_or(price, |compare_at_amount| {
+            compare_at_amount.amount.max(price)
+        });
+    let lowest_price = compare_at_price * (Decimal::ONE - maximum_markdown);
+    ((price - lowest_price) / price).max(Decimal::ZERO)
+}
+
+/// A discount value and its targets.
+pub(crate) type Group = (Value, Vec<Target>);
+
+/// The `value` with the `targets` that can take it, followed by smaller values
+/// with the targets that can't, so that no line is marked down by more than
+/// `maximum_markdown` percent of its compare-at price.
+pub(crate) fn cap(
+    value: Value,
+    targets: Vec<Target>,
+    cart_lines: &[input::CartLine],
+    maximum_markdown: f64,
+    currency_code: CurrencyCode,
+) -> Result<Vec<Group>, Box<dyn std::error::Error>> {
+    let maximum_markdown = Decimal::try_from(maximum_markdown)? / Decimal::ONE_HUNDRED;
+    let kind = Kind::new(&value)?;
+    let items: Vec<Item> = targets
+        .into_iter()
+        .filter_map(|target| item(target, cart_lines, maximum_markdown))
+        .collect();
+    let subtotal = subtotal(&items).ok_or(MoneyError::Overflow)?;
+    if subtotal <= Decimal::ZERO {
+        return Ok(vec![]);
+    }
+    let ceiling = kind.ceiling(subtotal).ok_or(MoneyError::Overflow)?;
+
+    let (full, mut capped): (Vec<Item>, Vec<Item>) = items
+        .into_iter()
+        .partition(|item| kind.limit(item) >= ceiling);
+    let mut discounts = vec![];
+    if !full.is_empty() {
+        let value = match kind {
+            Kind::Spread(_) if !capped.is_empty() => kind.value(ceiling, &full, currency_code)?,
+            _ => value,
+        };
+        discounts.push((value, full));
+    }
+    let rounded_limit = |item: &Item| kind.rounded(kind.limit(item), currency_code);
+    capped.sort_by_key(|item| Reverse(kind.limit(item)));
+    while let Some(first) = capped.first() {
+        let limit = rounded_limit(first);
+        let count = capped
+            .iter()
+            .take_while(|item| rounded_limit(item) == limit)
+            .count();
+        let group: Vec<Item> = capped.drain(..count).collect();
+        discounts.push((kind.value(limit, &group, currency_code)?, group));
+    }
+
+    Ok(discounts
+        .into_iter()
+        .filter(|(value, _)| !is_zero(value))
+        .map(|(value, items)| (value, items.into_iter().map(|item| item.target).collect()))
+        .collect())
+}
+
+/// The target's item, priced like its most marked down line with units.
+fn item(target: Target, cart_lines: &[input::CartLine], maximum_markdown: Decimal) -> Option<Item> {
+    let Target::ProductVariant { id, quantity } = &target;
+    let lines: Vec<&input::CartLine> = cart_lines
+        .iter()
+        .filter(|line| line.merchandise.id.as_ref() == Some(id) && line.quantity > 0)
+        .collect();
+    let line = lines
+        .iter()
+        .min_by_key(|line| allowance(&line.cost, maximum_markdown))?;
+    // Summed in `i64`, the lines of a variant can add up past `Int::MAX`.
+    let quantity = quantity.map_or_else(
+        || lines.iter().map(|line| i64::from(line.quantity)).sum(),
+        |quantity| i64::from(quantity.max(0)),
+    );
+    Some(Item {
+        price: line.cost.amount.amount,
+        quantity: quantity.into(),
+        allowance: allowance(&line.cost, maximum_markdown),
+        target,
+    })
+}
@@ -0,0 +1,348 @@
+//! Properties of the discount on random carts and configurations, including
+//! configurations the function has to fall back from.
+
+use proptest::prelude::*;
+use serde_json::{json, Value as Json};
+use shopify_function_core::{harness, strategies};
+
+use super::*;
+
+const VENDORS: [&str; 3] = ["Acme", "Globex", "Initech"];
+const PRODUCT_TYPES: [&str; 3] = ["Shirt", "Hat", "Gift card"];
+
+/// A product, the same for every line of a variant.
+fn product() -> impl Strategy<Value = Json> {
+    (
+        any::<[bool; 5]>(),
+        prop::option::of(prop::sample::select(&VENDORS[..])),
+        prop::option::of(prop::sample::select(&PRODUCT_TYPES[..])),
+    )
+        .prop_map(|(flags, vendor, product_type)| {
+            json!({
+                "inIncludedCollection": flags[0],
+                "inExcludedCollection": flags[1],
+                "hasIncludedTag": flags[2],
+                "hasExcludedTag": flags[3],
+                "isGiftCard": flags[4],
+                "vendor": vendor,
+                "productType": product_type,
+            })
+        })
+}
+
+/// A cart line, with the index of its variant among the 5 generated.
+fn cart_line() -> impl Strategy<Value = (Json, Option<usize>)> {
+    (
+        strategies::gid("CartLine", 100),
+        strategies::quantity(),
+        prop::option::weighted(0.9, 0..5usize),
+    )
+        .prop_map(|(id, quantity, variant)| (json!({ "id": id, "quantity": quantity }), variant))
+}
+
+/// The price of a variant, sometimes below its compare-at price.
+fn cost() -> impl Strategy<Value = Json> {
+    (strategies::amount(), prop::option::of(strategies::amount())).prop_map(
+        |(amount, compare_at_amount)| {
+            json!({
+                "amount": { "amount": amount },
+                "compareAtAmount": compare_at_amount.map(|amount| json!({ "amount": amount })),
+            })
+        },
+    )
+}
+
+/// Configurations, some of them out of the accepted ranges.
+fn configuration() -> impl Strategy<Value = Json> {
+    let quantity_limit = prop_oneof![
+        Just(Json::Null),
+        (-1..5i32).prop_map(|limit| json!({ "perVariant": limit })),
+        (-1..10i32).prop_map(|limit| json!({ "perOrder": limit })),
+    ];
+    (
+        -10.0..150.0f64,
+        prop::sample::select(vec!["percentage", "fixedAmount"]),
+        any::<bool>(),
+        prop::collection::vec(strategies::gid("ProductVariant", 5), 0..3),
+        quantity_limit,
+        prop::option::of(-1..10i32),
+        prop::option::of(0.0..500.0f64),
+        product_rules(),
+        currencies(),
+        (
+            prop::sample::select(vec!["minorUnit", "wholeUnit", "ninetyNine"]),
+            any::<bool>(),
+            prop::option::of(-10.0..110.0f64),
+        ),
+        strategies::messages(&["percent", "amount", "currency"]),
+    )
+        .prop_map(
+            |(
+                value,
+                discount_type,
+                applies_to_each_item,
+                excluded_variant_ids,
+                quantity_limit,
+                minimum_quantity,
+                minimum_subtotal,
+                mut product_rules,
+                currencies,
+                (rounding, exclude_sale_items, maximum_markdown),
+                messages,
+            )| {
+                let mut configuration = json!({
+                    "value": value,
+                    "discountType": discount_type,
+                    "appliesToEachItem": applies_to_each_item,
+                    "excludedVariantIds": excluded_variant_ids,
+                    "quantityLimit": quantity_limit,
+                    "minimumQuantity": minimum_quantity,
+                    "minimumSubtotal": minimum_subtotal,
+                    "currencies": currencies,
+                    "rounding": rounding,
+                    "excludeSaleItems": exclude_sale_items,
+                    "maximumMarkdown": maximum_markdown,
+                    "messages": messages,
+                });
+                // The product rules sit at the top level, like in the metafield.
+                let product_rules = product_rules.as_object_mut().unwrap();
+                configuration.as_object_mut().unwrap().append(product_rules);
+                configuration
+            },
+        )
+}
+
+fn product_rules() -> impl Strategy<Value = Json> {
+    let names = |names: &'static [&'static str]| prop::sample::subsequence(names, 0..=1);
+    (
+        prop::collection::vec(strategies::gid("Collection", 3), 0..2),
+        prop::collection::vec(strategies::gid("Collection", 3), 0..2),
+        prop::collection::vec("[a-z]{1,8}", 0..2),
+        prop::collection::vec("[a-z]{1,8}", 0..2),
+        (names(&VENDORS), names(&VENDORS)),
+        (names(&PRODUCT_TYPES), names(&PRODUCT_TYPES)),
+        prop::sample::select(vec!["included", "excluded", "only"]),
+    )
+        .prop_map(
+            |(
+                included_collection_ids,
+                excluded_collection_ids,
+                included_tags,
+                excluded_tags,
+                (included_vendors, excluded_vendors),
+                (included_product_types, excluded_product_types),
+                gift_cards,
+            )| {
+                json!({
+                    "includedCollectionIds": included_collection_ids,
+                    "excludedCollectionIds": excluded_collection_ids,
+                    "includedTags": included_tags,
+                    "excludedTags": excluded_tags,
+                    "includedVendors": included_vendors,
+                    "excludedVendors": excluded_vendors,
+                    "includedProductTypes": included_product_types,
+                    "excludedProductTypes": excluded_product_types,
+                    "giftCards": gift_cards,
+                })
+            },
+        )
+}
+
+const CURRENCY_CODES: [&str; 4] = ["USD", "CAD", "EUR", "JPY"];
+
+/// Overrides of the amounts for some currencies.
+fn currencies() -> impl Strategy<Value = Json> {
+    let overrides = (
+        prop::option::of(-10.0..2000.0f64),
+        prop::option::of(-10.0..5000.0f64),
+    )
+        .prop_map(|(value, minimum_subtotal)| {
+            json!({ "value": value, "minimumSubtotal": minimum_subtotal })
+        });
+    prop::collection::btree_map(prop::sample::select(&CURRENCY_CODES[..]), overrides, 0..3)
+        .prop_map(|currencies| json!(currencies))
+}
+
+fn input() -> impl Strategy<Value = input::Input> {
+    (
+        prop::sample::select(&CURRENCY_CODES[..]),
+        prop::option::of((1..200_000u32).prop_map(|rate| format!("{}", rate as f64 / 1000.0))),
+        (
+            prop::collection::vec(product(), 5),
+            prop::collection::vec(cost(), 5),
+        ),
+        prop::collection::vec(cart_line(), 0..8),
+        strategies::language_code(),
+        strategies::metafield(configuration()),
+    )
+        .prop_map(
+            |(currency_code, rate, (products, costs), lines, language_code, metafield)| {
+                let lines: Vec<Json> = lines
+                    .into_iter()
+                    .map(|(mut line, variant)| {
+                        line["cost"] = costs[variant.unwrap_or_default()].clone();
+                        line["merchandise"] = variant.map_or(json!({}), |n| {
+                            json!({
+                                "id": format!("gid://shopify/ProductVariant/{}", n),
+                                "product": products[n],
+                            })
+                        });
+                        line
+                    })
+                    .collect();
+                serde_json::from_value(json!({
+                    "cart": {
+                        "cost": { "subtotalAmount": { "currencyCode": currency_code } },
+                        "lines": lines,
+                    },
+                    "presentmentCurrencyRate": rate,
+                    "localization": { "language": { "isoCode": language_code } },
+                    "discountNode": { "metafield": metafield },
+                }))
+                .expect("the input deserializes")
+            },
+        )
+}
+
+fn lines_of<'a>(
+    lines: &'a [input::CartLine],
+    id: &'a ID,
+) -> impl Iterator<Item = &'a input::CartLine> {
+    lines
+        .iter()
+        .filter(move |line| line.merchandise.id.as_ref() == Some(id))
+}
+
+#[test]
+fn test_results_are_valid() {
+    harness::check(include_str!("../schema.graphql"), input(), function);
+}
+
+proptest! {
+    #[test]
+    fn test_targets_are_eligible_cart_variants(input in input()) {
+        let config = input.configuration().unwrap();
+        let result = function(input.clone()).unwrap();
+
+        for discount in &result.discounts {
+            for Target::ProductVariant { id, .. } in &discount.targets {
+                prop_assert!(!config.excluded_variant_ids.contains(id), "{} is excluded", id);
+                let merchandise = input
+                    .cart
+                    .lines
+                    .iter()
+                    .map(|line| &line.merchandise)
+                    .find(|merchandise| merchandise.id.as_ref() == Some(id));
+                prop_assert!(merchandise.is_some(), "{} isn't in the cart", id);
+                let product = merchandise.and_then(|merchandise| merchandise.product.as_ref()).unwrap();
+                let rules = &config.products;
+                prop_assert!(!product.vendor.as_ref().is_some_and(|vendor| rules.excluded_vendors.contains(vendor)));
+                prop_assert!(!product.product_type.as_ref().is_some_and(|product_type| rules.excluded_product_types.contains(product_type)));
+                prop_assert!(rules.excluded_collection_ids.is_empty() || !product.in_excluded_collection);
+                prop_assert!(rules.excluded_tags.is_empty() || !product.has_excluded_tag);
+                match rules.gift_cards {
+                    GiftCards::Included => {}
+                    GiftCards::Excluded => prop_assert!(!product.is_gift_card),
+                    GiftCards::Only => prop_assert!(product.is_gift_card),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_quantity_limit(input in input()) {
+        let config = input.configuration().unwrap();
+        let result = function(input.clone()).unwrap();
+
+        let quantities: Vec<Int> = result
+            .discounts
+            .iter()
+            .flat_map(|discount| &discount.targets)
+            .map(|Target::ProductVariant { quantity, .. }| quantity.unwrap_or(Int::MAX))
+            .collect();
+        match config.quantity_limit {
+            Some(QuantityLimit::PerVariant(limit)) => {
+                prop_assert!(quantities.iter().all(|quantity| (1..=limit).contains(quantity)));
+            }
+            Some(QuantityLimit::PerOrder(limit)) => {
+                prop_assert!(quantities.iter().all(|quantity| *quantity > 0));
+                prop_assert!(quantities.iter().sum::<Int>() <= limit);
+            }
+            None => prop_assert!(quantities.iter().all(|quantity| *quantity == Int::MAX)),
+        }
+    }
+
+    #[test]
+    fn test_messages_are_filled_in(input in input()) {
+        let result = function(input).unwrap();
+
+        for discount in &result.discounts {
+            let placeholders: &[&str] = match discount.value {
+                Value::Percentage(_) => &["{percent}"],
+                Value::FixedAmount(_) => &["{amount}", "{currency}"],
+            };
+            if let Some(message) = &discount.message {
+                for placeholder in placeholders {
+                    prop_assert!(!message.contains(placeholder), "{}", message);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_sale_items_are_excluded(input in input()) {
+        let config = input.configuration().unwrap();
+        let result = function(input.clone()).unwrap();
+
+        if config.exclude_sale_items {
+            for Target::ProductVariant { id, .. } in result.discounts.iter().flat_map(|discount| &discount.targets) {
+                let mut lines = lines_of(&input.cart.lines, id);
+                prop_assert!(lines.any(|line| !is_on_sale(&line.cost)), "{} is only on sale", id);
+            }
+        }
+    }
+
+    #[test]
+    fn test_markdown_is_capped(input in input()) {
+        let config = input.configuration().unwrap();
+        let result = function(input.clone()).unwrap();
+
+        let maximum_markdown = match config.maximum_markdown {
+            Some(maximum_markdown) => Decimal::try_from(maximum_markdown).unwrap() / Decimal::ONE_HUNDRED,
+            None => return Ok(()),
+        };
+        for discount in &result.discounts {
+            // A fixed amount spread across the targets takes the same part off
+            // each. The lines of a variant all have its price.
+            let targets_subtotal: Decimal = discount
+                .targets
+                .iter()
+                .map(|Target::ProductVariant { id, quantity }| {
+                    let price = lines_of(&input.cart.lines, id).next().unwrap().cost.amount.amount;
+                    let quantity = quantity.unwrap_or_else(|| lines_of(&input.cart.lines, id).map(|line| line.quantity).sum());
+                    price * Decimal::from(quantity)
+                })
+                .sum();
+            for Target::ProductVariant { id, .. } in &discount.targets {
+                for line in lines_of(&input.cart.lines, id) {
+                    let price = line.cost.amount.amount;
+                    let discounted_price = match &discount.value {
+                        Value::Percentage(Percentage { value }) => {
+                            price * (Decimal::ONE - Decimal::try_from(*value).unwrap() / Decimal::ONE_HUNDRED)
+                        }
+                        Value::FixedAmount(FixedAmount { amount, applies_to_each_item: Some(true) }) => price - amount,
+                        Value::FixedAmount(FixedAmount { amount, .. }) => price * (Decimal::ONE - amount / targets_subtotal),
+                    };
+                    let compare_at_price = line.cost.compare_at_amount.as_ref().map_or(price, |amount| amount.amount.max(price));
+                    prop_assert!(
+                        discounted_price >= compare_at_price * (Decimal::ONE - maximum_markdown),
+                        "{} is marked down from {} to {}",
+                        id,
+                        compare_at_price,
+                        discounted_price
+                    );
+                }
+            }
+        }
+    }
+}
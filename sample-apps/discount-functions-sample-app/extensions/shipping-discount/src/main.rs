@@ -1,69 +1,172 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
+use shopify_function_core::config;
+use shopify_function_core::messages::Messages;
+use shopify_function_core::shipping_discounts::*;
+use shopify_function_core::{CurrencyCode, Money};
+
+mod input {
+    #![allow(dead_code)]
+    include!(concat!(env!("OUT_DIR"), "/input.rs"));
+}
 
-mod api;
-use api::*;
+#[cfg(test)]
+mod properties;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// The delivery groups the discount applies to. Each empty list matches any
+/// delivery group.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Configuration {
-    pub value: f64,
+    /// The percentage off shipping. Without one, shipping is free over the
+    /// minimum subtotal, and half off when there's none.
+    #[serde(default)]
+    pub value: Option<f64>,
+    #[serde(default)]
+    pub country_codes: Vec<input::CountryCode>,
+    #[serde(default)]
+    pub delivery_method_types: Vec<input::DeliveryMethod>,
+    /// Titles or codes of the delivery options, e.g. `"Express"`.
+    #[serde(default)]
+    pub delivery_options: Vec<String>,
+    /// Only discount carts whose subtotal reaches the threshold of their
+    /// currency, e.g. `{ "CAD": 100, "USD": 75 }`. Carts in other currencies
+    /// aren't discounted.
+    #[serde(default)]
+    pub minimum_subtotal: Option<HashMap<CurrencyCode, Decimal>>,
+    /// The message per language, e.g. `"{percent}% off shipping"`.
+    #[serde(default)]
+    pub messages: Messages,
 }
 
 impl Configuration {
     pub const DEFAULT_VALUE: f64 = 50.0;
+    pub const DEFAULT_VALUE_OVER_MINIMUM_SUBTOTAL: f64 = 100.0;
+
+    pub fn value(&self) -> f64 {
+        match (self.value, &self.minimum_subtotal) {
+            (Some(value), _) => value,
+            (None, Some(_)) => Self::DEFAULT_VALUE_OVER_MINIMUM_SUBTOTAL,
+            (None, None) => Self::DEFAULT_VALUE,
+        }
+    }
+
+    fn applies_to_cart(&self, cart: &input::Cart) -> bool {
+        let thresholds = match &self.minimum_subtotal {
+            Some(thresholds) => thresholds,
+            None => return true,
+        };
+        let subtotal = Money::new(
+            cart.cost.subtotal_amount.amount,
+            cart.cost.subtotal_amount.currency_code,
+        );
+        match thresholds.get(&subtotal.currency_code) {
+            Some(threshold) => subtotal >= Money::new(*threshold, subtotal.currency_code),
+            None => false,
+        }
+    }
+
+    fn applies_to(&self, delivery_group: &input::CartDeliveryGroup) -> bool {
+        if !self.country_codes.is_empty() {
+            let country_code = delivery_group
+                .delivery_address
+                .as_ref()
+                .and_then(|address| address.country_code);
+            if !country_code.is_some_and(|code| self.country_codes.contains(&code)) {
+                return false;
+            }
+        }
+        // Until the buyer picks an option, the group only qualifies if
+        // whichever option they pick does.
+        match &delivery_group.selected_delivery_option {
+            Some(option) => self.applies_to_option(option),
+            None => {
+                !delivery_group.delivery_options.is_empty()
+                    && delivery_group
+                        .delivery_options
+                        .iter()
+                        .all(|option| self.applies_to_option(option))
+            }
+        }
+    }
 
-    fn from_str(str: &str) -> Self {
-        serde_json::from_str(str).expect("Unable to parse configuration value from metafield")
+    fn applies_to_option(&self, option: &input::CartDeliveryOption) -> bool {
+        let method_type_matches = self.delivery_method_types.is_empty()
+            || self
+                .delivery_method_types
+                .contains(&option.delivery_method_type);
+        let option_matches = self.delivery_options.is_empty()
+            || [&option.title, &option.code]
+                .into_iter()
+                .flatten()
+                .any(|name| self.delivery_options.contains(name));
+        method_type_matches && option_matches
     }
 }
 
-impl Default for Configuration {
-    fn default() -> Self {
-        Configuration {
-            value: Self::DEFAULT_VALUE,
+impl config::Validate for Configuration {
+    fn validate(&self) -> Result<(), config::ConfigurationError> {
+        config::validate_percentage("value", self.value())?;
+        for threshold in self.minimum_subtotal.iter().flat_map(HashMap::values) {
+            if *threshold < Decimal::ZERO {
+                return Err(config::ConfigurationError::OutOfRange {
+                    field: "minimumSubtotal",
+                    value: f64::try_from(*threshold).unwrap_or(f64::NEG_INFINITY),
+                    expected: "an amount greater than or equal to 0",
+                });
+            }
         }
+        config::Validate::validate(&self.messages)
     }
 }
 
 impl input::Input {
-    pub fn configuration(&self) -> Configuration {
-        match &self.discount_node.metafield {
-            Some(input::Metafield { value }) => Configuration::from_str(value),
-            None => Configuration::default(),
-        }
+    pub fn configuration(&self) -> Result<Configuration, config::ConfigurationError> {
+        let value = self
+            .discount_node
+            .metafield
+            .as_ref()
+            .map(|metafield| metafield.value.as_str());
+        config::load(value, config::Mode::FailOpen)
     }
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let input: input::Input = serde_json::from_reader(std::io::BufReader::new(std::io::stdin()))?;
-    let mut out = std::io::stdout();
-    let mut serializer = serde_json::Serializer::new(&mut out);
-    function(input)?.serialize(&mut serializer)?;
-    Ok(())
+fn main() -> std::process::ExitCode {
+    shopify_function_core::run(function)
 }
 
-fn function(input: input::Input) -> Result<FunctionResult, Box<dyn std::error::Error>> {
+pub(crate) fn function(input: input::Input) -> Result<FunctionResult, Box<dyn std::error::Error>> {
     let delivery_groups = &input.cart.delivery_groups;
-    let config = input.configuration();
-    let targets = targets(delivery_groups);
-    Ok(build_result(config.value, targets))
+    let config = input.configuration()?;
+    let targets = if config.applies_to_cart(&input.cart) {
+        targets(delivery_groups, &config)
+    } else {
+        vec![]
+    };
+    let message = config.messages.render(
+        input.localization.language.iso_code,
+        &[("percent", config.value().to_string())],
+    );
+    Ok(build_result(config.value(), message, targets))
 }
 
-fn targets(delivery_groups: &[input::CartDeliveryGroup]) -> Vec<Target> {
+fn targets(delivery_groups: &[input::CartDeliveryGroup], config: &Configuration) -> Vec<Target> {
     delivery_groups
         .iter()
+        .filter(|delivery_group| config.applies_to(delivery_group))
         .map(|delivery_group| Target::DeliveryGroup {
             id: delivery_group.id.to_string(),
         })
         .collect()
 }
 
-fn build_result(value: f64, targets: Vec<Target>) -> FunctionResult {
+fn build_result(value: f64, message: Option<String>, targets: Vec<Target>) -> FunctionResult {
     let discounts = if targets.is_empty() {
         vec![]
     } else {
         vec![Discount {
-            message: None,
-            conditions: None,
+            message,
             targets,
             value: Value::Percentage(Percentage { value }),
         }]
@@ -77,6 +180,40 @@ fn build_result(value: f64, targets: Vec<Target>) -> FunctionResult {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use shopify_function_core::fixtures;
+    use shopify_function_core::messages::LanguageCode;
+    use shopify_function_core::validate;
+
+    fn function(input: input::Input) -> Result<FunctionResult, Box<dyn std::error::Error>> {
+        validate::checked(include_str!("../schema.graphql"), super::function, input)
+    }
+
+    fn delivery_option(title: &str, method: input::DeliveryMethod) -> input::CartDeliveryOption {
+        input::CartDeliveryOption {
+            code: Some(title.to_lowercase()),
+            title: Some(title.to_string()),
+            delivery_method_type: method,
+        }
+    }
+
+    fn delivery_group(
+        id: &str,
+        country_code: input::CountryCode,
+        selected_delivery_option: Option<input::CartDeliveryOption>,
+    ) -> input::CartDeliveryGroup {
+        input::CartDeliveryGroup {
+            id: id.to_string(),
+            delivery_address: Some(input::MailingAddress {
+                country_code: Some(country_code),
+            }),
+            selected_delivery_option,
+            delivery_options: vec![
+                delivery_option("Standard", input::DeliveryMethod::Shipping),
+                delivery_option("Express", input::DeliveryMethod::Shipping),
+                delivery_option("Pickup", input::DeliveryMethod::PickUp),
+            ],
+        }
+    }
 
     fn input(configuration: Option<Configuration>) -> input::Input {
         let discount_node = input::DiscountNode {
@@ -87,9 +224,22 @@ mod tests {
         };
         input::Input {
             cart: input::Cart {
-                delivery_groups: vec![input::CartDeliveryGroup {
-                    id: String::from("gid://shopify/CartDeliveryGroup/0"),
-                }],
+                cost: input::CartCost {
+                    subtotal_amount: input::MoneyV2 {
+                        amount: Decimal::new(800, 1),
+                        currency_code: CurrencyCode::Cad,
+                    },
+                },
+                delivery_groups: vec![delivery_group(
+                    "gid://shopify/CartDeliveryGroup/0",
+                    input::CountryCode::Ca,
+                    Some(delivery_option("Express", input::DeliveryMethod::Shipping)),
+                )],
+            },
+            localization: input::Localization {
+                language: input::Language {
+                    iso_code: LanguageCode::En,
+                },
             },
             discount_node,
         }
@@ -114,7 +264,10 @@ mod tests {
 
     #[test]
     fn test_discount_with_value() {
-        let input = input(Some(Configuration { value: 10.0 }));
+        let input = input(Some(Configuration {
+            value: Some(10.0),
+            ..Configuration::default()
+        }));
         let result = serde_json::json!(function(input).unwrap());
 
         let expected_result = serde_json::json!({
@@ -134,6 +287,7 @@ mod tests {
         let input = input::Input {
             cart: input::Cart {
                 delivery_groups: vec![],
+                ..input(None).cart
             },
             ..input(Some(Configuration::default()))
         };
@@ -145,4 +299,270 @@ mod tests {
         });
         assert_eq!(handle_result, expected_handle_result);
     }
+
+    #[test]
+    fn test_discount_with_negative_value() {
+        let input = input(Some(Configuration {
+            value: Some(-10.0),
+            ..Configuration::default()
+        }));
+        let result = serde_json::json!(function(input).unwrap());
+
+        let expected_result = serde_json::json!({
+            "discounts": [{
+                "targets": [
+                    { "deliveryGroup": { "id": "gid://shopify/CartDeliveryGroup/0" } },
+                ],
+                "value": { "percentage": { "value": 50.0 } },
+            }],
+            "discountApplicationStrategy": "FIRST",
+        });
+        assert_eq!(result, expected_result);
+    }
+
+    fn delivery_group_ids(
+        config: Configuration,
+        delivery_groups: &[input::CartDeliveryGroup],
+    ) -> Vec<ID> {
+        targets(delivery_groups, &config)
+            .into_iter()
+            .map(|Target::DeliveryGroup { id }| id)
+            .collect()
+    }
+
+    #[test]
+    fn test_discount_for_countries() {
+        let delivery_groups = vec![
+            delivery_group(
+                "gid://shopify/CartDeliveryGroup/0",
+                input::CountryCode::Ca,
+                None,
+            ),
+            delivery_group(
+                "gid://shopify/CartDeliveryGroup/1",
+                input::CountryCode::Us,
+                None,
+            ),
+        ];
+        let config = Configuration {
+            country_codes: vec![input::CountryCode::Ca],
+            ..Configuration::default()
+        };
+
+        assert_eq!(
+            delivery_group_ids(config, &delivery_groups),
+            vec!["gid://shopify/CartDeliveryGroup/0"]
+        );
+    }
+
+    #[test]
+    fn test_discount_for_selected_delivery_option() {
+        let delivery_groups = vec![
+            delivery_group(
+                "gid://shopify/CartDeliveryGroup/0",
+                input::CountryCode::Ca,
+                Some(delivery_option("Express", input::DeliveryMethod::Shipping)),
+            ),
+            delivery_group(
+                "gid://shopify/CartDeliveryGroup/1",
+                input::CountryCode::Ca,
+                Some(delivery_option("Standard", input::DeliveryMethod::Shipping)),
+            ),
+            delivery_group(
+                "gid://shopify/CartDeliveryGroup/2",
+                input::CountryCode::Ca,
+                None,
+            ),
+        ];
+        let config = Configuration {
+            delivery_options: vec!["express".to_string()],
+            ..Configuration::default()
+        };
+
+        assert_eq!(
+            delivery_group_ids(config, &delivery_groups),
+            vec!["gid://shopify/CartDeliveryGroup/0"]
+        );
+    }
+
+    #[test]
+    fn test_discount_without_selected_delivery_option() {
+        let delivery_groups = vec![delivery_group(
+            "gid://shopify/CartDeliveryGroup/0",
+            input::CountryCode::Ca,
+            None,
+        )];
+        // Pickup doesn't qualify, so the group isn't discounted until the
+        // buyer picks a shipping option.
+        let config = Configuration {
+            delivery_method_types: vec![input::DeliveryMethod::Shipping],
+            ..Configuration::default()
+        };
+        assert!(delivery_group_ids(config, &delivery_groups).is_empty());
+
+        let config = Configuration {
+            delivery_method_types: vec![
+                input::DeliveryMethod::Shipping,
+                input::DeliveryMethod::PickUp,
+            ],
+            ..Configuration::default()
+        };
+        assert_eq!(
+            delivery_group_ids(config, &delivery_groups),
+            vec!["gid://shopify/CartDeliveryGroup/0"]
+        );
+
+        let delivery_groups = vec![input::CartDeliveryGroup {
+            delivery_options: vec![],
+            ..delivery_groups[0].clone()
+        }];
+        assert!(delivery_group_ids(Configuration::default(), &delivery_groups).is_empty());
+    }
+
+    #[test]
+    fn test_discount_for_delivery_method_types() {
+        let delivery_groups = vec![
+            delivery_group(
+                "gid://shopify/CartDeliveryGroup/0",
+                input::CountryCode::Ca,
+                Some(delivery_option("Pickup", input::DeliveryMethod::PickUp)),
+            ),
+            delivery_group(
+                "gid://shopify/CartDeliveryGroup/1",
+                input::CountryCode::Ca,
+                Some(delivery_option("Express", input::DeliveryMethod::Shipping)),
+            ),
+        ];
+        let config = Configuration {
+            delivery_method_types: vec![input::DeliveryMethod::PickUp],
+            ..Configuration::default()
+        };
+
+        assert_eq!(
+            delivery_group_ids(config, &delivery_groups),
+            vec!["gid://shopify/CartDeliveryGroup/0"]
+        );
+    }
+
+    #[test]
+    fn test_express_shipping_in_canada_from_metafield() {
+        let configuration: Configuration = serde_json::from_str(
+            r#"{
+                "value": 100,
+                "countryCodes": ["CA"],
+                "deliveryMethodTypes": ["SHIPPING"],
+                "deliveryOptions": ["Express"]
+            }"#,
+        )
+        .unwrap();
+        let delivery_groups = vec![
+            delivery_group(
+                "gid://shopify/CartDeliveryGroup/0",
+                input::CountryCode::Ca,
+                Some(delivery_option("Express", input::DeliveryMethod::Shipping)),
+            ),
+            delivery_group(
+                "gid://shopify/CartDeliveryGroup/1",
+                input::CountryCode::Us,
+                Some(delivery_option("Express", input::DeliveryMethod::Shipping)),
+            ),
+        ];
+
+        assert_eq!(
+            delivery_group_ids(configuration, &delivery_groups),
+            vec!["gid://shopify/CartDeliveryGroup/0"]
+        );
+    }
+
+    #[test]
+    fn test_free_shipping_over_threshold() {
+        let configuration: Configuration = serde_json::from_str(
+            r#"{ "value": 100, "minimumSubtotal": { "CAD": 75, "USD": 100 } }"#,
+        )
+        .unwrap();
+        let discounted = |amount: &str, currency_code| {
+            let input = input(Some(configuration.clone()));
+            let input = input::Input {
+                cart: input::Cart {
+                    cost: input::CartCost {
+                        subtotal_amount: input::MoneyV2 {
+                            amount: amount.parse().unwrap(),
+                            currency_code,
+                        },
+                    },
+                    ..input.cart
+                },
+                ..input
+            };
+            !function(input).unwrap().discounts.is_empty()
+        };
+
+        assert!(discounted("80.0", CurrencyCode::Cad));
+        assert!(discounted("75.0", CurrencyCode::Cad));
+        assert!(!discounted("80.0", CurrencyCode::Usd));
+        assert!(!discounted("1000.0", CurrencyCode::Eur));
+    }
+
+    #[test]
+    fn test_free_shipping_is_the_default_over_threshold() {
+        let configuration: Configuration =
+            serde_json::from_str(r#"{ "minimumSubtotal": { "CAD": "75.00" } }"#).unwrap();
+        let result = serde_json::json!(function(input(Some(configuration))).unwrap());
+
+        assert_eq!(
+            result["discounts"][0]["value"],
+            serde_json::json!({ "percentage": { "value": 100.0 } })
+        );
+    }
+
+    #[test]
+    fn test_negative_threshold_is_invalid() {
+        let configuration: Configuration =
+            serde_json::from_str(r#"{ "value": 100, "minimumSubtotal": { "CAD": -1 } }"#).unwrap();
+
+        assert_eq!(
+            config::Validate::validate(&configuration)
+                .unwrap_err()
+                .to_string(),
+            "configuration `minimumSubtotal` is -1, expected an amount greater than or equal to 0"
+        );
+    }
+
+    #[test]
+    fn test_message_in_buyer_language() {
+        let configuration: Configuration = serde_json::from_str(
+            r#"{
+                "value": 25,
+                "messages": {
+                    "templates": {
+                        "EN": "{percent}% off shipping",
+                        "FR": "{percent} % de rabais sur la livraison"
+                    },
+                    "fallbackLanguage": "EN"
+                }
+            }"#,
+        )
+        .unwrap();
+        let message = |iso_code| {
+            let input = input::Input {
+                localization: input::Localization {
+                    language: input::Language { iso_code },
+                },
+                ..input(Some(configuration.clone()))
+            };
+            function(input).unwrap().discounts[0].message.clone()
+        };
+
+        assert_eq!(message(LanguageCode::En).unwrap(), "25% off shipping");
+        assert_eq!(
+            message(LanguageCode::Fr).unwrap(),
+            "25 % de rabais sur la livraison"
+        );
+        assert_eq!(message(LanguageCode::Es).unwrap(), "25% off shipping");
+    }
+
+    #[test]
+    fn test_fixtures() {
+        fixtures::check("tests/fixtures", function);
+    }
 }
@@ -0,0 +1,160 @@
+//! Properties of the discount on random delivery groups and configurations,
+//! including configurations the function has to fall back from.
+
+use proptest::prelude::*;
+use serde_json::{json, Value as Json};
+use shopify_function_core::{harness, strategies};
+
+use super::*;
+
+const COUNTRY_CODES: [&str; 3] = ["CA", "US", "FR"];
+const CURRENCY_CODES: [&str; 3] = ["CAD", "USD", "EUR"];
+const DELIVERY_METHODS: [&str; 3] = ["SHIPPING", "PICK_UP", "LOCAL"];
+const DELIVERY_OPTIONS: [&str; 3] = ["Standard", "Express", "Pickup"];
+
+fn delivery_option() -> impl Strategy<Value = Json> {
+    (
+        prop::sample::select(&DELIVERY_OPTIONS[..]),
+        prop::sample::select(&DELIVERY_METHODS[..]),
+    )
+        .prop_map(|(title, method)| {
+            json!({
+                "code": title.to_lowercase(),
+                "title": title,
+                "deliveryMethodType": method,
+            })
+        })
+}
+
+/// A delivery group, without the `id` that has to be unique in the cart.
+fn delivery_group() -> impl Strategy<Value = Json> {
+    (
+        prop::option::weighted(0.9, prop::sample::select(&COUNTRY_CODES[..])),
+        prop::option::of(delivery_option()),
+        prop::collection::vec(delivery_option(), 0..4),
+    )
+        .prop_map(
+            |(country_code, selected_delivery_option, delivery_options)| {
+                json!({
+                    "deliveryAddress": { "countryCode": country_code },
+                    "selectedDeliveryOption": selected_delivery_option,
+                    "deliveryOptions": delivery_options,
+                })
+            },
+        )
+}
+
+/// Configurations, some of them out of the accepted ranges.
+fn configuration() -> impl Strategy<Value = Json> {
+    (
+        prop::option::of(-10.0..110.0f64),
+        prop::sample::subsequence(&COUNTRY_CODES[..], 0..=2),
+        prop::sample::subsequence(&DELIVERY_METHODS[..], 0..=2),
+        prop::sample::subsequence(&DELIVERY_OPTIONS[..], 0..=2),
+        prop::option::of(prop::collection::btree_map(
+            prop::sample::select(&CURRENCY_CODES[..]),
+            -10.0..500.0f64,
+            0..3,
+        )),
+        strategies::messages(&["percent"]),
+    )
+        .prop_map(
+            |(
+                value,
+                country_codes,
+                delivery_method_types,
+                delivery_options,
+                minimum_subtotal,
+                messages,
+            )| {
+                json!({
+                    "value": value,
+                    "countryCodes": country_codes,
+                    "deliveryMethodTypes": delivery_method_types,
+                    "deliveryOptions": delivery_options,
+                    "minimumSubtotal": minimum_subtotal,
+                    "messages": messages,
+                })
+            },
+        )
+}
+
+fn input() -> impl Strategy<Value = input::Input> {
+    (
+        strategies::amount(),
+        prop::sample::select(&CURRENCY_CODES[..]),
+        prop::collection::vec(delivery_group(), 0..4),
+        strategies::language_code(),
+        strategies::metafield(configuration()),
+    )
+        .prop_map(
+            |(amount, currency_code, mut delivery_groups, language_code, metafield)| {
+                for (n, delivery_group) in delivery_groups.iter_mut().enumerate() {
+                    delivery_group["id"] = json!(format!("gid://shopify/CartDeliveryGroup/{}", n));
+                }
+                serde_json::from_value(json!({
+                    "cart": {
+                        "cost": {
+                            "subtotalAmount": { "amount": amount, "currencyCode": currency_code },
+                        },
+                        "deliveryGroups": delivery_groups,
+                    },
+                    "localization": { "language": { "isoCode": language_code } },
+                    "discountNode": { "metafield": metafield },
+                }))
+                .expect("the input deserializes")
+            },
+        )
+}
+
+#[test]
+fn test_results_are_valid() {
+    harness::check(include_str!("../schema.graphql"), input(), function);
+}
+
+proptest! {
+    #[test]
+    fn test_targets_are_eligible_delivery_groups(input in input()) {
+        let config = input.configuration().unwrap();
+        let result = function(input.clone()).unwrap();
+
+        for discount in &result.discounts {
+            for Target::DeliveryGroup { id } in &discount.targets {
+                let delivery_group = input.cart.delivery_groups.iter().find(|group| &group.id == id);
+                prop_assert!(delivery_group.is_some(), "{} isn't in the cart", id);
+                let country_code = delivery_group
+                    .and_then(|group| group.delivery_address.as_ref())
+                    .and_then(|address| address.country_code);
+                prop_assert!(
+                    config.country_codes.is_empty()
+                        || country_code.is_some_and(|code| config.country_codes.contains(&code)),
+                    "{} ships to {:?}",
+                    id,
+                    country_code
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_other_currencies_are_not_discounted(input in input()) {
+        let config = input.configuration().unwrap();
+        let currency_code = input.cart.cost.subtotal_amount.currency_code;
+        let result = function(input).unwrap();
+
+        if let Some(thresholds) = &config.minimum_subtotal {
+            if !thresholds.contains_key(&currency_code) {
+                prop_assert!(result.discounts.is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn test_messages_are_filled_in(input in input()) {
+        let result = function(input).unwrap();
+
+        for message in result.discounts.iter().filter_map(|discount| discount.message.as_ref()) {
+            prop_assert!(!message.contains("{percent}"), "{}", message);
+        }
+    }
+}
@@ -0,0 +1,29 @@
+import { useState } from 'react';
+import { TextField } from '@shopify/polaris';
+
+/**
+ * A comma-separated list, parsed when the field loses focus so commas can be
+ * typed.
+ */
+export default function ListField({ label, values, onChange }) {
+  const [text, setText] = useState(values.join(', '));
+
+  const handleBlur = () => {
+    onChange(
+      text
+        .split(',')
+        .map((value) => value.trim())
+        .filter(Boolean),
+    );
+  };
+
+  return (
+    <TextField
+      label={label}
+      value={text}
+      onChange={setText}
+      onBlur={handleBlur}
+      helpText="Separate values with commas"
+    />
+  );
+}
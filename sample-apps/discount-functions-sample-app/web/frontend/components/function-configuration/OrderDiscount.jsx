@@ -8,6 +8,7 @@ import {
   TextStyle,
 } from '@shopify/polaris';
 
+import ListField from '../ListField';
 import VariantList from '../VariantList';
 
 export default function OrderDiscount({
@@ -23,6 +24,20 @@ export default function OrderDiscount({
     });
   };
 
+  const handleMinimumSubtotalChange = (value) => {
+    onConfigurationChange({
+      ...configuration,
+      minimumSubtotal: value === '' ? null : parseFloat(value),
+    });
+  };
+
+  const handleCustomerTagsChange = (customerTags) => {
+    onConfigurationChange({
+      ...configuration,
+      customerTags,
+    });
+  };
+
   const handleVariantPickerSelection = ({ selection }) => {
     const excludedVariantIds = selection.map((item) => item.id);
 
@@ -77,6 +92,19 @@ export default function OrderDiscount({
           min={0}
           max={100}
         />
+        <TextField
+          label="Minimum subtotal"
+          value={configuration.minimumSubtotal ?? ''}
+          onChange={handleMinimumSubtotalChange}
+          type="number"
+          min={0}
+          helpText="Excluded variants don't count towards it. Leave blank to discount any order"
+        />
+        <ListField
+          label="Customer tags"
+          values={configuration.customerTags ?? []}
+          onChange={handleCustomerTagsChange}
+        />
         <Stack spacing="tight" vertical>
           <TextContainer>Excluded variant IDs:</TextContainer>
           {excludedVariantIdsMarkup}
@@ -92,4 +120,6 @@ export default function OrderDiscount({
 export const DEFAULT_CONFIGURATION = {
   value: 0,
   excludedVariantIds: [],
+  minimumSubtotal: null,
+  customerTags: [],
 };
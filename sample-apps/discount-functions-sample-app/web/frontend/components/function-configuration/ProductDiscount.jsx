@@ -1,6 +1,8 @@
 import { useState, useMemo } from 'react';
 import { ResourcePicker } from '@shopify/app-bridge-react';
 import {
+  Checkbox,
+  Select,
   Stack,
   TextField,
   TextContainer,
@@ -8,6 +10,7 @@ import {
   TextStyle,
 } from '@shopify/polaris';
 
+import ListField from '../ListField';
 import VariantList from '../VariantList';
 
 export default function ProductDiscount({
@@ -15,6 +18,7 @@ export default function ProductDiscount({
   onConfigurationChange,
 }) {
   const [showVariantPicker, setShowVariantPicker] = useState(false);
+  const [collectionPickerField, setCollectionPickerField] = useState(null);
 
   const handleValueChange = (value) => {
     onConfigurationChange({
@@ -23,6 +27,27 @@ export default function ProductDiscount({
     });
   };
 
+  const handleMinimumChange = (field, parse) => (value) => {
+    onConfigurationChange({
+      ...configuration,
+      [field]: value === '' ? null : parse(value),
+    });
+  };
+
+  const handleDiscountTypeChange = (discountType) => {
+    onConfigurationChange({
+      ...configuration,
+      discountType,
+    });
+  };
+
+  const handleAppliesToEachItemChange = (appliesToEachItem) => {
+    onConfigurationChange({
+      ...configuration,
+      appliesToEachItem,
+    });
+  };
+
   const handleVariantPickerSelection = ({ selection }) => {
     const excludedVariantIds = selection.map((item) => item.id);
 
@@ -34,6 +59,22 @@ export default function ProductDiscount({
     setShowVariantPicker(false);
   };
 
+  const handleCollectionPickerSelection = ({ selection }) => {
+    onConfigurationChange({
+      ...configuration,
+      [collectionPickerField]: selection.map((item) => item.id),
+    });
+
+    setCollectionPickerField(null);
+  };
+
+  const handleProductRuleChange = (field) => (value) => {
+    onConfigurationChange({
+      ...configuration,
+      [field]: value,
+    });
+  };
+
   const { excludedVariantIds, excludedVariantIdsMarkup } = useMemo(() => {
     if (!configuration.excludedVariantIds.length) {
       return {
@@ -54,6 +95,8 @@ export default function ProductDiscount({
     return { excludedVariantIds, excludedVariantIdsMarkup };
   }, [configuration.excludedVariantIds]);
 
+  const discountType = configuration.discountType ?? 'percentage';
+
   const resourcePickerMarkup = showVariantPicker && (
     <ResourcePicker
       resourceType="ProductVariant"
@@ -65,17 +108,92 @@ export default function ProductDiscount({
     />
   );
 
+  const collectionPickerMarkup = collectionPickerField && (
+    <ResourcePicker
+      resourceType="Collection"
+      initialSelectionIds={(configuration[collectionPickerField] ?? []).map(
+        (id) => ({ id }),
+      )}
+      onSelection={handleCollectionPickerSelection}
+      onCancel={() => setCollectionPickerField(null)}
+      allowMultiple
+      open
+    />
+  );
+
+  const collectionsMarkup = (field, label) => {
+    const count = (configuration[field] ?? []).length;
+    return (
+      <Stack spacing="tight" alignment="center">
+        <TextContainer>
+          {label}: {count ? `${count} selected` : 'None'}
+        </TextContainer>
+        <Button onClick={() => setCollectionPickerField(field)}>
+          Select collections
+        </Button>
+      </Stack>
+    );
+  };
+
+  const listFieldMarkup = (field, label) => (
+    <ListField
+      label={label}
+      values={configuration[field] ?? []}
+      onChange={handleProductRuleChange(field)}
+    />
+  );
+
   return (
     <>
       {resourcePickerMarkup}
+      {collectionPickerMarkup}
       <Stack vertical>
+        <Select
+          label="Discount type"
+          options={DISCOUNT_TYPE_OPTIONS}
+          value={discountType}
+          onChange={handleDiscountTypeChange}
+        />
+        {discountType === 'fixedAmount' ? (
+          <>
+            <TextField
+              label="Amount off"
+              value={configuration.value}
+              onChange={handleValueChange}
+              type="number"
+              min={0}
+            />
+            <Checkbox
+              label="Apply to each eligible item"
+              checked={configuration.appliesToEachItem ?? false}
+              onChange={handleAppliesToEachItemChange}
+            />
+          </>
+        ) : (
+          <TextField
+            label="Percentage off"
+            value={configuration.value}
+            onChange={handleValueChange}
+            type="number"
+            min={0}
+            max={100}
+          />
+        )}
         <TextField
-          label="Percentage off"
-          value={configuration.value}
-          onChange={handleValueChange}
+          label="Minimum quantity"
+          value={configuration.minimumQuantity ?? ''}
+          onChange={handleMinimumChange('minimumQuantity', parseInt)}
           type="number"
           min={0}
-          max={100}
+          helpText="Leave blank to apply the discount to any quantity"
+        />
+        <TextField
+          label="Minimum subtotal"
+          value={configuration.minimumSubtotal ?? ''}
+          onChange={handleMinimumChange('minimumSubtotal', parseFloat)}
+          type="number"
+          min={0}
+          helpText="Leave blank to apply the discount to any subtotal"
         />
         <Stack spacing="tight" vertical>
           <TextContainer>Excluded variant IDs:</TextContainer>
@@ -84,12 +202,72 @@ export default function ProductDiscount({
             Select variant
           </Button>
         </Stack>
+        <TextContainer>
+          Only products matching an included rule are discounted, unless none
+          are set. Products matching an excluded rule never are.
+        </TextContainer>
+        {collectionsMarkup('includedCollectionIds', 'Included collections')}
+        {collectionsMarkup('excludedCollectionIds', 'Excluded collections')}
+        {listFieldMarkup('includedTags', 'Included product tags')}
+        {listFieldMarkup('excludedTags', 'Excluded product tags')}
+        {listFieldMarkup('includedVendors', 'Included vendors')}
+        {listFieldMarkup('excludedVendors', 'Excluded vendors')}
+        {listFieldMarkup('includedProductTypes', 'Included product types')}
+        {listFieldMarkup('excludedProductTypes', 'Excluded product types')}
+        <Select
+          label="Gift cards"
+          options={GIFT_CARDS_OPTIONS}
+          value={configuration.giftCards ?? 'included'}
+          onChange={handleProductRuleChange('giftCards')}
+        />
+        <Checkbox
+          label="Exclude items already on sale"
+          checked={configuration.excludeSaleItems ?? false}
+          onChange={handleProductRuleChange('excludeSaleItems')}
+          helpText="Items priced below their compare-at price aren't discounted"
+        />
+        <TextField
+          label="Maximum markdown"
+          value={configuration.maximumMarkdown ?? ''}
+          onChange={handleMinimumChange('maximumMarkdown', parseFloat)}
+          type="number"
+          min={0}
+          max={100}
+          suffix="%"
+          helpText="The most an item can be marked down from its compare-at price, sale and discount together. Items that would go over it aren't discounted unless no item can take the whole discount. Leave blank for no limit"
+        />
       </Stack>
     </>
   );
 }
 
+const DISCOUNT_TYPE_OPTIONS = [
+  { label: 'Percentage', value: 'percentage' },
+  { label: 'Fixed amount', value: 'fixedAmount' },
+];
+
+const GIFT_CARDS_OPTIONS = [
+  { label: 'Discount gift cards like other products', value: 'included' },
+  { label: 'Never discount gift cards', value: 'excluded' },
+  { label: 'Only discount gift cards', value: 'only' },
+];
+
 export const DEFAULT_CONFIGURATION = {
   value: 0,
+  discountType: 'percentage',
+  appliesToEachItem: false,
   excludedVariantIds: [],
+  minimumQuantity: null,
+  minimumSubtotal: null,
+  includedCollectionIds: [],
+  excludedCollectionIds: [],
+  includedTags: [],
+  excludedTags: [],
+  includedVendors: [],
+  excludedVendors: [],
+  includedProductTypes: [],
+  excludedProductTypes: [],
+  giftCards: 'included',
+  excludeSaleItems: false,
+  maximumMarkdown: null,
 };